@@ -0,0 +1,100 @@
+//! ADC capable pins that can be wired to potentiometers
+use stm32f1xx_hal::adc::Adc;
+use stm32f1xx_hal::gpio::{Analog, Cr, PA0, PA2, PA3, PA4, PA5, PA6, PB0, PB1};
+use stm32f1xx_hal::pac::ADC1;
+use stm32f1xx_hal::prelude::*;
+
+/// ADC1 channels which are not used by USB, the LED or the buttons
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum AdcPin {
+    Pa0,
+    Pa2,
+    Pa3,
+    Pa4,
+    Pa5,
+    Pa6,
+    Pb0,
+    Pb1,
+}
+
+/// Single pin configured in analog mode
+pub enum AnalogPin {
+    Pa0(PA0<Analog>),
+    Pa2(PA2<Analog>),
+    Pa3(PA3<Analog>),
+    Pa4(PA4<Analog>),
+    Pa5(PA5<Analog>),
+    Pa6(PA6<Analog>),
+    Pb0(PB0<Analog>),
+    Pb1(PB1<Analog>),
+}
+
+impl AnalogPin {
+    pub fn read(&mut self, adc: &mut Adc<ADC1>) -> u16 {
+        match self {
+            Self::Pa0(pin) => adc.read(pin),
+            Self::Pa2(pin) => adc.read(pin),
+            Self::Pa3(pin) => adc.read(pin),
+            Self::Pa4(pin) => adc.read(pin),
+            Self::Pa5(pin) => adc.read(pin),
+            Self::Pa6(pin) => adc.read(pin),
+            Self::Pb0(pin) => adc.read(pin),
+            Self::Pb1(pin) => adc.read(pin),
+        }
+        .unwrap_or_default()
+    }
+}
+
+/// Pool of all ADC capable pins. Pins are handed out once, by [`AdcPin`]
+pub struct AnalogPins {
+    pa0: Option<PA0<Analog>>,
+    pa2: Option<PA2<Analog>>,
+    pa3: Option<PA3<Analog>>,
+    pa4: Option<PA4<Analog>>,
+    pa5: Option<PA5<Analog>>,
+    pa6: Option<PA6<Analog>>,
+    pb0: Option<PB0<Analog>>,
+    pb1: Option<PB1<Analog>>,
+}
+
+impl AnalogPins {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        pa0: PA0,
+        pa2: PA2,
+        pa3: PA3,
+        pa4: PA4,
+        pa5: PA5,
+        pa6: PA6,
+        gpioa_crl: &mut Cr<'A', false>,
+        pb0: PB0,
+        pb1: PB1,
+        gpiob_crl: &mut Cr<'B', false>,
+    ) -> Self {
+        // unused pins are left in analog mode as well, which is also their lowest power state
+        Self {
+            pa0: Some(pa0.into_analog(gpioa_crl)),
+            pa2: Some(pa2.into_analog(gpioa_crl)),
+            pa3: Some(pa3.into_analog(gpioa_crl)),
+            pa4: Some(pa4.into_analog(gpioa_crl)),
+            pa5: Some(pa5.into_analog(gpioa_crl)),
+            pa6: Some(pa6.into_analog(gpioa_crl)),
+            pb0: Some(pb0.into_analog(gpiob_crl)),
+            pb1: Some(pb1.into_analog(gpiob_crl)),
+        }
+    }
+
+    /// Takes the pin out of the pool, returns `None` if it has been already taken
+    pub fn take(&mut self, pin: AdcPin) -> Option<AnalogPin> {
+        match pin {
+            AdcPin::Pa0 => self.pa0.take().map(AnalogPin::Pa0),
+            AdcPin::Pa2 => self.pa2.take().map(AnalogPin::Pa2),
+            AdcPin::Pa3 => self.pa3.take().map(AnalogPin::Pa3),
+            AdcPin::Pa4 => self.pa4.take().map(AnalogPin::Pa4),
+            AdcPin::Pa5 => self.pa5.take().map(AnalogPin::Pa5),
+            AdcPin::Pa6 => self.pa6.take().map(AnalogPin::Pa6),
+            AdcPin::Pb0 => self.pb0.take().map(AnalogPin::Pb0),
+            AdcPin::Pb1 => self.pb1.take().map(AnalogPin::Pb1),
+        }
+    }
+}
@@ -0,0 +1,30 @@
+//! Annunciator lamps driven by the host, e.g. "reverser unlocked" or "gear in transit"
+//!
+//! The host lights them through the joystick output report, see [`crate::hid::OutputReport`].
+//! Lamps are wired from the pin to ground through a resistor, so a high pin lights its lamp.
+use embedded_hal::digital::v2::OutputPin;
+
+pub struct Annunciators<P, const LAMPS: usize> {
+    pins: [P; LAMPS],
+}
+
+impl<P: OutputPin, const LAMPS: usize> Annunciators<P, LAMPS> {
+    pub fn new(mut pins: [P; LAMPS]) -> Self {
+        assert!(LAMPS <= 8);
+        for pin in pins.iter_mut() {
+            pin.set_low().ok();
+        }
+        Self { pins }
+    }
+
+    /// Bit `n` of `lamps` lights lamp `n`
+    pub fn set(&mut self, lamps: u8) {
+        for (lamp, pin) in self.pins.iter_mut().enumerate() {
+            if lamps & 1 << lamp != 0 {
+                pin.set_high().ok();
+            } else {
+                pin.set_low().ok();
+            }
+        }
+    }
+}
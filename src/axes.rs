@@ -0,0 +1,107 @@
+//! Analog axes layout
+//!
+//! Every axis reported to the host is listed in [`AXES`], in report order. Joystick report,
+//! report descriptor and stored calibration are all derived from this table.
+use crate::analog::AdcPin;
+use crate::filter::GlitchFilter;
+use usbd_human_interface_device::page::{Desktop, Simulation};
+
+/// Maximum amount of axes, one per [`AdcPin`] except the one of [`PARAMETER_POT`]
+pub const MAX_AXES: usize = 7;
+
+/// Usage an axis is reported as. Simulation usages let simulators recognise e.g. the throttle,
+/// while generic desktop usages are understood by every host
+#[derive(Clone, Copy)]
+pub enum AxisUsage {
+    Desktop(Desktop),
+    Simulation(Simulation),
+}
+
+impl AxisUsage {
+    /// Extended `Usage` item carrying its usage page, so axes of different pages can be reported
+    /// by a single main item
+    pub const fn item(self) -> [u8; 5] {
+        let (page, id) = match self {
+            AxisUsage::Desktop(usage) => (0x01, usage as u8),
+            AxisUsage::Simulation(usage) => (0x02, usage as u8),
+        };
+        [0x0b, id, 0x00, page, 0x00]
+    }
+}
+
+pub struct AxisDefinition {
+    /// Pin where the axis potentiometer is connected
+    pub pin: AdcPin,
+    /// Usage the axis is reported as
+    pub usage: AxisUsage,
+    /// Engine throttle which follows [`SYNC_MASTER`] while throttle sync is active
+    pub engine_throttle: bool,
+    /// First effect of the axis chain, rejects spikes of worn potentiometers before smoothing,
+    /// e.g. `GlitchFilter::Median(Median::new(5))` or
+    /// `GlitchFilter::SlewReject(SlewReject::new(200, 3))`
+    pub glitch_filter: GlitchFilter,
+}
+
+pub const AXES: [AxisDefinition; 3] = [
+    // throttle
+    AxisDefinition {
+        pin: AdcPin::Pa3,
+        usage: AxisUsage::Desktop(Desktop::X),
+        engine_throttle: true,
+        glitch_filter: GlitchFilter::None,
+    },
+    // propeller pitch
+    AxisDefinition {
+        pin: AdcPin::Pa0,
+        usage: AxisUsage::Desktop(Desktop::Y),
+        engine_throttle: false,
+        glitch_filter: GlitchFilter::None,
+    },
+    // mixture
+    AxisDefinition {
+        pin: AdcPin::Pa2,
+        usage: AxisUsage::Desktop(Desktop::Z),
+        engine_throttle: false,
+        glitch_filter: GlitchFilter::None,
+    },
+];
+
+pub const AXES_COUNT: usize = AXES.len();
+
+/// Reports the joystick as a flight simulation device instead of a generic desktop joystick,
+/// pair it with [`AxisUsage::Simulation`] usages such as `Simulation::Throttle`. Windows only
+/// lists generic desktop joysticks and gamepads as game controllers
+pub const FLIGHT_SIMULATION_DEVICE: bool = false;
+
+/// Index of the lever which drives all engine throttles while throttle sync is active
+pub const SYNC_MASTER: usize = 0;
+
+/// Pin of the potentiometer which adjusts [`crate::parameters::Parameters`]
+pub const PARAMETER_POT: AdcPin = AdcPin::Pa4;
+
+/// Usage of the extra axis reported when the parameter potentiometer is used as an axis. It's
+/// reported after all axes from [`AXES`]
+pub const PARAMETER_AXIS_USAGE: Desktop = Desktop::Slider;
+
+/// Whether every axis has its own pin, other than the one of [`PARAMETER_POT`]
+const fn pins_are_distinct() -> bool {
+    let mut i = 0;
+    while i < AXES_COUNT {
+        if AXES[i].pin as u8 == PARAMETER_POT as u8 {
+            return false;
+        }
+        let mut j = i + 1;
+        while j < AXES_COUNT {
+            if AXES[i].pin as u8 == AXES[j].pin as u8 {
+                return false;
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+    true
+}
+
+const _: () = assert!(AXES_COUNT > 0 && AXES_COUNT <= MAX_AXES);
+const _: () = assert!(pins_are_distinct(), "an ADC pin is assigned twice");
+const _: () = assert!(SYNC_MASTER < AXES_COUNT && AXES[SYNC_MASTER].engine_throttle);
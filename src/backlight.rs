@@ -0,0 +1,86 @@
+//! WS2812 panel backlight
+//!
+//! The strip is fed by SPI2 at 3 MHz through DMA, every WS2812 bit is sent as three SPI bits:
+//! `110` for a one and `100` for a zero. Frames are encoded here and sent by the main loop
+//! whenever the previous transfer has finished.
+//!
+//! The panel is lit in the colour from [`crate::config::Config`]. Calibration and parameter
+//! potentiometer role selection replace it with their own colours, and a potentiometer fault
+//! lights the strip red.
+
+/// LEDs on the strip
+pub const BACKLIGHT_LEDS: usize = 8;
+/// SPI bytes per LED, 24 bits of colour at three SPI bits each
+const BYTES_PER_LED: usize = 9;
+/// Low bytes after a frame, newer WS2812B latch the frame after 280 µs of low line
+const RESET_BYTES: usize = 108;
+pub const FRAME_LEN: usize = BACKLIGHT_LEDS * BYTES_PER_LED + RESET_BYTES;
+/// Milliseconds between frames
+pub const FRAME_INTERVAL: u32 = 20;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct Rgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Rgb {
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b }
+    }
+
+    const OFF: Self = Self::new(0, 0, 0);
+    const FAULT: Self = Self::new(255, 0, 0);
+    const CALIBRATION_LOW: Self = Self::new(0, 0, 255);
+    const CALIBRATION_HIGH: Self = Self::new(0, 255, 0);
+    const ROLE_SELECT: Self = Self::new(255, 120, 0);
+
+    fn dim(self, brightness: u8) -> Self {
+        let dim = |value: u8| (u16::from(value) * u16::from(brightness) / 255) as u8;
+        Self::new(dim(self.r), dim(self.g), dim(self.b))
+    }
+}
+
+/// What the strip shows
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum BacklightState {
+    /// Panel lit in its configured colour
+    Panel,
+    Fault,
+    CalibrationLow,
+    CalibrationHigh,
+    /// First `n` LEDs lit, one per parameter potentiometer role
+    RoleSelect(u8),
+}
+
+/// Colours of the strip in `state`, panel colour is dimmed to `brightness`
+pub fn frame(state: BacklightState, color: Rgb, brightness: u8) -> [Rgb; BACKLIGHT_LEDS] {
+    match state {
+        BacklightState::Panel => [color.dim(brightness); BACKLIGHT_LEDS],
+        BacklightState::Fault => [Rgb::FAULT; BACKLIGHT_LEDS],
+        BacklightState::CalibrationLow => [Rgb::CALIBRATION_LOW; BACKLIGHT_LEDS],
+        BacklightState::CalibrationHigh => [Rgb::CALIBRATION_HIGH; BACKLIGHT_LEDS],
+        BacklightState::RoleSelect(count) => core::array::from_fn(|led| {
+            if led < usize::from(count) {
+                Rgb::ROLE_SELECT
+            } else {
+                Rgb::OFF
+            }
+        }),
+    }
+}
+
+/// Encodes `leds` into SPI bytes, the reset bytes at the end are left low
+pub fn encode(leds: &[Rgb; BACKLIGHT_LEDS], buffer: &mut [u8; FRAME_LEN]) {
+    for (led, bytes) in leds.iter().zip(buffer.chunks_exact_mut(BYTES_PER_LED)) {
+        // WS2812 takes green first
+        let color = u32::from(led.g) << 16 | u32::from(led.r) << 8 | u32::from(led.b);
+        let mut bits: u128 = 0;
+        for bit in (0..24).rev() {
+            bits = bits << 3 | if color & 1 << bit != 0 { 0b110 } else { 0b100 };
+        }
+        bytes.copy_from_slice(&bits.to_be_bytes()[16 - BYTES_PER_LED..]);
+    }
+    buffer[BACKLIGHT_LEDS * BYTES_PER_LED..].fill(0);
+}
@@ -1,46 +1,90 @@
 //use cortex_m_semihosting::hprintln;
 use embedded_hal::digital::v2::InputPin;
+/// Milliseconds after a state change during which further changes are ignored
 const DEBOUNCE_DELAY: u32 = 20;
 
+/// Debounced state of a single switch
+#[derive(Clone, Copy, Default)]
+pub struct Debounce {
+    state: bool,
+    /// Time of the last accepted state change, in milliseconds
+    last_change: u32,
+}
+
+impl Debounce {
+    pub const fn new() -> Self {
+        Self {
+            state: false,
+            last_change: 0,
+        }
+    }
+
+    pub fn state(&self) -> bool {
+        self.state
+    }
+
+    /// Feeds the raw switch state, returns `true` if the debounced state has changed
+    pub fn update(&mut self, state: bool, now: u32) -> bool {
+        //hprintln!("{} {}", now, self.last_change);
+        if state != self.state && now.wrapping_sub(self.last_change) > DEBOUNCE_DELAY {
+            self.state = state;
+            self.last_change = now;
+            return true;
+        }
+
+        false
+    }
+}
+
+/// Raw state of a switch read by [`Button`], pins with a pull-up are pressed when low
+pub trait SwitchInput {
+    /// Current raw state, `true` when pressed
+    fn is_pressed(&self) -> bool;
+
+    /// Pops the oldest change captured since the last call as `(pressed, timestamp)`, inputs
+    /// which are only polled have none
+    fn pop_change(&mut self) -> Option<(bool, u32)> {
+        None
+    }
+}
+
+impl<P: InputPin> SwitchInput for P {
+    fn is_pressed(&self) -> bool {
+        self.is_low().unwrap_or_default()
+    }
+}
+
 pub struct Button<P> {
     pub pin: P,
-    old_state: bool,
-    /// Ticks elapsed since last press
-    last_change: u32,
-    current: u32,
+    debounce: Debounce,
 }
 
-impl<P: InputPin> Button<P> {
+impl<P: SwitchInput> Button<P> {
     pub fn new(pin: P) -> Self {
         Self {
             pin,
-            old_state: false,
-            last_change: 0,
-            current: 0,
+            debounce: Debounce::new(),
         }
     }
 
-    pub fn toggle(&mut self) -> bool {
-        self.click();
-        self.old_state
+    pub fn toggle(&mut self, now: u32) -> bool {
+        self.click(now);
+        self.debounce.state()
     }
 
     pub fn pressed(&self) -> bool {
-        self.pin.is_low().unwrap_or_default()
-    }
-
-    pub fn click(&mut self) -> bool {
-        self.current = self.current.wrapping_add(1);
-        let state = self.pin.is_low().unwrap_or_default();
-        //hprintln!("{} {}", self.current, self.last_change);
-        if state != self.old_state {
-            if self.current - self.last_change > DEBOUNCE_DELAY {
-                self.old_state = state;
-                self.last_change = self.current;
-                return state;
-            }
+        self.pin.is_pressed()
+    }
+
+    /// `now` is a monotonic millisecond timestamp, e.g. [`crate::clock::millis`]. Captured
+    /// changes are replayed at their own timestamps before the current state is sampled
+    pub fn click(&mut self, now: u32) -> bool {
+        let mut clicked = false;
+        while let Some((state, at)) = self.pin.pop_change() {
+            clicked |= self.debounce.update(state, at) && state;
         }
 
-        false
+        let state = self.pin.is_pressed();
+        clicked | (self.debounce.update(state, now) && state)
     }
 }
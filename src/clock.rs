@@ -0,0 +1,27 @@
+//! Monotonic millisecond clock driven by SysTick
+use core::sync::atomic::{AtomicU32, Ordering};
+use cortex_m::peripheral::syst::SystClkSource;
+use cortex_m::peripheral::SYST;
+use cortex_m_rt::exception;
+use stm32f1xx_hal::rcc::Clocks;
+
+static MILLIS: AtomicU32 = AtomicU32::new(0);
+
+/// Starts SysTick interrupt at 1 kHz
+pub fn start(mut syst: SYST, clocks: &Clocks) {
+    syst.set_clock_source(SystClkSource::Core);
+    syst.set_reload(clocks.sysclk().raw() / 1000 - 1);
+    syst.clear_current();
+    syst.enable_counter();
+    syst.enable_interrupt();
+}
+
+/// Milliseconds since [`start`], wraps around after ~49 days so compare with `wrapping_sub`
+pub fn millis() -> u32 {
+    MILLIS.load(Ordering::Relaxed)
+}
+
+#[exception]
+fn SysTick() {
+    MILLIS.fetch_add(1, Ordering::Relaxed);
+}
@@ -0,0 +1,450 @@
+use crate::axes::AXES_COUNT;
+use crate::backlight::Rgb;
+use crate::hid::BUTTONS;
+use crate::keymap::{KeyBinding, DEFAULT_KEY_BINDINGS, KEY_BINDING_WORDS, MAX_KEY_BINDINGS};
+use crate::latch::DEFAULT_LATCHING;
+use crate::media::{MediaBinding, DEFAULT_MEDIA_BINDINGS, MAX_MEDIA_BINDINGS, MEDIA_BINDING_WORDS};
+use crate::parameters::{Parameters, PotRole};
+use crate::shift::{ShiftLayer, DEFAULT_SHIFT_BUTTON, LOGICAL_BUTTONS};
+use axis::Axis;
+use heapless::Vec;
+use stm32f1xx_hal::flash::FlashWriter;
+
+const FLASH_BASE: usize = 0x0800_0000;
+const LAST_PAGE_ADDRESS: usize = 0x0800_F800;
+/// Bumped whenever stored words change meaning. Words appended at the end read as erased in
+/// configs saved by older firmware and get their defaults
+const CONFIG_MAGIC: u16 = 0x0aaab;
+/// Magic of the first firmware, which only stored the calibration of 3 axes
+const LEGACY_MAGIC: u16 = 0x0aaaa;
+/// Magic word and [`AXES_COUNT`], configs saved with another axis table are not loaded
+const HEADER_WORDS: usize = 2;
+/// Header, min and max of every axis, parameter potentiometer role and parameters, shift
+/// button, shift mapping packed two buttons per word, latching buttons mask, backlight brightness
+/// backlight colour packed as red and green, then blue, keyboard bindings and media bindings
+const CONFIG_WORDS: usize = HEADER_WORDS
+    + AXES_COUNT * 2
+    + 5
+    + 1
+    + LOGICAL_BUTTONS / 2
+    + 2
+    + 1
+    + 2
+    + MAX_KEY_BINDINGS * KEY_BINDING_WORDS
+    + MAX_MEDIA_BINDINGS * MEDIA_BINDING_WORDS;
+/// Warm white
+const DEFAULT_BACKLIGHT_COLOR: Rgb = Rgb::new(255, 160, 60);
+/// Value of erased flash, words past the end of a config saved by older firmware read as this
+const ERASED_WORD: u16 = 0xffff;
+/// Stored as the shift button while the shift layer is disabled, an erased word is a config
+/// saved before the shift layer existed
+const SHIFT_DISABLED: u16 = 0x00ff;
+/// Stored in the first word of empty binding slots, configs saved before the bindings existed
+/// have every slot erased
+const EMPTY_SLOT: u16 = 0xfffe;
+
+fn word_or(word: u16, default: u16) -> u16 {
+    if word == ERASED_WORD {
+        default
+    } else {
+        word
+    }
+}
+
+fn shift_button_or_default(word: u16) -> Option<u8> {
+    if word == ERASED_WORD {
+        DEFAULT_SHIFT_BUTTON
+    } else {
+        u8::try_from(word)
+            .ok()
+            .filter(|&button| usize::from(button) < LOGICAL_BUTTONS)
+    }
+}
+
+/// Latching mask from its low and high words. Configs saved by older firmware have both words
+/// erased and get [`DEFAULT_LATCHING`]
+fn latching_or_default(low: u16, high: u16) -> u32 {
+    if low == ERASED_WORD && high == ERASED_WORD {
+        DEFAULT_LATCHING
+    } else {
+        u32::from(low) | u32::from(high) << 16
+    }
+}
+
+/// `N` bindings of `W` words each from `words`. Configs saved by older firmware have every slot
+/// erased and get `defaults`
+fn bindings_or_default<T, const N: usize, const W: usize>(
+    words: &[u16],
+    defaults: [Option<T>; N],
+    from_words: fn(&[u16]) -> Option<T>,
+) -> [Option<T>; N] {
+    if words[..N * W].iter().all(|&word| word == ERASED_WORD) {
+        defaults
+    } else {
+        core::array::from_fn(|i| from_words(&words[i * W..(i + 1) * W]))
+    }
+}
+
+/// Words of an empty binding slot
+fn empty_slot<const W: usize>() -> [u16; W] {
+    let mut words = [ERASED_WORD; W];
+    words[0] = EMPTY_SLOT;
+    words
+}
+
+fn backlight_color_or_default(red_green: u16, blue: u16) -> Rgb {
+    if red_green == ERASED_WORD && blue == ERASED_WORD {
+        DEFAULT_BACKLIGHT_COLOR
+    } else {
+        let [r, g] = red_green.to_le_bytes();
+        Rgb::new(r, g, blue as u8)
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct AxisCalibration {
+    pub min: u16,
+    pub max: u16,
+}
+
+impl Default for AxisCalibration {
+    fn default() -> Self {
+        Self {
+            min: 3300,
+            max: 4090,
+        }
+    }
+}
+
+#[repr(C)]
+pub struct Config {
+    /// Calibrated range of each axis, in [`crate::axes::AXES`] order
+    pub calibration: [AxisCalibration; AXES_COUNT],
+    /// What the parameter potentiometer controls
+    pub pot_role: PotRole,
+    pub parameters: Parameters,
+    pub shift: ShiftLayer,
+    /// Mask of logical buttons wired to latching toggle switches, see [`crate::latch`]
+    pub latching: u32,
+    /// Colour of the panel backlight, see [`crate::backlight`]
+    pub backlight_color: Rgb,
+    /// Keyboard shortcuts, see [`crate::keymap`]
+    pub key_bindings: [Option<KeyBinding>; MAX_KEY_BINDINGS],
+    /// Consumer control and telephony actions, see [`crate::media`]
+    pub media_bindings: [Option<MediaBinding>; MAX_MEDIA_BINDINGS],
+}
+
+impl Config {
+    pub fn new(writer: &FlashWriter) -> Self {
+        // TODO: Proper error handling
+        // FIXME: Reinterpret struct instead of extracting fields manually?
+        let base_offset = LAST_PAGE_ADDRESS - FLASH_BASE; // 0xF800
+        let values = writer.read(base_offset as u32, CONFIG_WORDS * 2).unwrap();
+        let data: Vec<u16, CONFIG_WORDS> = values
+            .chunks_exact(2)
+            .map(|chunk| u16::from_le_bytes([chunk[0], chunk[1]]))
+            .collect();
+        Self::from_words(&data)
+    }
+
+    /// Config stored as `data`, the default config if `data` doesn't hold one
+    fn from_words(data: &[u16]) -> Self {
+        if data[0] == LEGACY_MAGIC && AXES_COUNT == 3 {
+            return Self {
+                calibration: core::array::from_fn(|i| AxisCalibration {
+                    min: data[1 + i * 2],
+                    max: data[2 + i * 2],
+                }),
+                ..Self::default()
+            };
+        }
+
+        if data[0] == CONFIG_MAGIC && usize::from(data[1]) == AXES_COUNT {
+            //hprintln!("Configuration loaded! {:?}", data);
+            let tail = &data[HEADER_WORDS + AXES_COUNT * 2..];
+            let defaults = Parameters::default();
+            Self {
+                calibration: core::array::from_fn(|i| AxisCalibration {
+                    min: data[HEADER_WORDS + i * 2],
+                    max: data[HEADER_WORDS + 1 + i * 2],
+                }),
+                pot_role: PotRole::from_word(tail[0]).unwrap_or_default(),
+                parameters: Parameters {
+                    filter: word_or(tail[1], defaults.filter),
+                    deadzone: word_or(tail[2], defaults.deadzone),
+                    curve: word_or(tail[3], defaults.curve),
+                    axis: word_or(tail[4], defaults.axis),
+                    backlight: word_or(tail[8 + LOGICAL_BUTTONS / 2], defaults.backlight),
+                },
+                shift: ShiftLayer {
+                    button: shift_button_or_default(tail[5]),
+                    map: core::array::from_fn(|i| {
+                        let button = tail[6 + i / 2].to_le_bytes()[i % 2];
+                        if usize::from(button) < BUTTONS {
+                            button
+                        } else {
+                            ShiftLayer::default_mapping(i)
+                        }
+                    }),
+                },
+                latching: latching_or_default(
+                    tail[6 + LOGICAL_BUTTONS / 2],
+                    tail[7 + LOGICAL_BUTTONS / 2],
+                ),
+                backlight_color: backlight_color_or_default(
+                    tail[9 + LOGICAL_BUTTONS / 2],
+                    tail[10 + LOGICAL_BUTTONS / 2],
+                ),
+                key_bindings: bindings_or_default::<_, MAX_KEY_BINDINGS, KEY_BINDING_WORDS>(
+                    &tail[11 + LOGICAL_BUTTONS / 2..],
+                    DEFAULT_KEY_BINDINGS,
+                    KeyBinding::from_words,
+                ),
+                media_bindings: bindings_or_default::<_, MAX_MEDIA_BINDINGS, MEDIA_BINDING_WORDS>(
+                    &tail[11 + LOGICAL_BUTTONS / 2 + MAX_KEY_BINDINGS * KEY_BINDING_WORDS..],
+                    DEFAULT_MEDIA_BINDINGS,
+                    MediaBinding::from_words,
+                ),
+            }
+        } else {
+            //hprintln!("Loading default config");
+            Self::default()
+        }
+    }
+
+    pub fn save(&self, writer: &mut FlashWriter) {
+        // TODO: Proper error handling
+        // FIXME: Reinterpret struct instead of extracting fields manually?
+        let base_offset = LAST_PAGE_ADDRESS - FLASH_BASE; // 0xF800
+        let data = self.to_words();
+        writer.page_erase(base_offset as u32).unwrap();
+
+        //hprintln!("writing...");
+        for (i, val) in data.iter().enumerate() {
+            let addr = base_offset as u32 + i as u32 * 2;
+            writer.write(addr, &val.to_le_bytes()).unwrap();
+        }
+
+        //hprintln!("done!");
+    }
+
+    /// Words stored by [`Config::save`]
+    fn to_words(&self) -> Vec<u16, CONFIG_WORDS> {
+        let mut data: Vec<u16, CONFIG_WORDS> = Vec::new();
+        data.push(CONFIG_MAGIC).unwrap();
+        data.push(AXES_COUNT as u16).unwrap();
+        for calibration in &self.calibration {
+            data.push(calibration.min).unwrap();
+            data.push(calibration.max).unwrap();
+        }
+        data.push(self.pot_role as u16).unwrap();
+        data.push(self.parameters.filter).unwrap();
+        data.push(self.parameters.deadzone).unwrap();
+        data.push(self.parameters.curve).unwrap();
+        data.push(self.parameters.axis).unwrap();
+        let shift_button = self.shift.button.map_or(SHIFT_DISABLED, u16::from);
+        data.push(shift_button).unwrap();
+        for pair in self.shift.map.chunks_exact(2) {
+            data.push(u16::from_le_bytes([pair[0], pair[1]])).unwrap();
+        }
+        for word in [self.latching as u16, (self.latching >> 16) as u16] {
+            data.push(word).unwrap();
+        }
+        data.push(self.parameters.backlight).unwrap();
+        let color = self.backlight_color;
+        data.push(u16::from_le_bytes([color.r, color.g])).unwrap();
+        data.push(u16::from(color.b)).unwrap();
+        for binding in &self.key_bindings {
+            let words = binding.map_or_else(empty_slot, |binding| binding.to_words());
+            data.extend_from_slice(&words).unwrap();
+        }
+        for binding in &self.media_bindings {
+            let words = binding.map_or_else(empty_slot, |binding| binding.to_words());
+            data.extend_from_slice(&words).unwrap();
+        }
+        data
+    }
+
+    pub fn new_axes(&self) -> [Axis; AXES_COUNT] {
+        self.calibration
+            .map(|calibration| Axis::new(calibration.min, calibration.max, false))
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            calibration: [AxisCalibration::default(); AXES_COUNT],
+            pot_role: PotRole::default(),
+            parameters: Parameters::default(),
+            shift: ShiftLayer::default(),
+            latching: DEFAULT_LATCHING,
+            backlight_color: DEFAULT_BACKLIGHT_COLOR,
+            key_bindings: DEFAULT_KEY_BINDINGS,
+            media_bindings: DEFAULT_MEDIA_BINDINGS,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::gesture::Gesture;
+    use crate::keymap::{KeyCombo, Trigger, MACRO_STEPS};
+    use crate::media::MediaUsage;
+    use usbd_human_interface_device::page::{Consumer, Keyboard, Telephony};
+
+    /// Index of the shift button word
+    const SHIFT_BUTTON_WORD: usize = HEADER_WORDS + AXES_COUNT * 2 + 5;
+    /// Index of the low latching mask word, the high word follows
+    const LATCHING_WORD: usize = SHIFT_BUTTON_WORD + 1 + LOGICAL_BUTTONS / 2;
+    /// Index of the first word of the key bindings
+    const KEY_BINDINGS_WORD: usize = LATCHING_WORD + 5;
+    /// Index of the first word of the media bindings
+    const MEDIA_BINDINGS_WORD: usize = KEY_BINDINGS_WORD + MAX_KEY_BINDINGS * KEY_BINDING_WORDS;
+
+    fn round_trip(config: &Config) -> Config {
+        Config::from_words(&config.to_words())
+    }
+
+    #[test]
+    fn shift_layer_round_trip() {
+        let mut config = Config::default();
+        config.shift.button = Some(3);
+        config.shift.map[5] = 40;
+        let loaded = round_trip(&config);
+        assert_eq!(loaded.shift.button, Some(3));
+        assert_eq!(loaded.shift.map, config.shift.map);
+
+        config.shift.button = None;
+        assert_eq!(round_trip(&config).shift.button, None);
+    }
+
+    #[test]
+    fn shift_button_defaults_for_older_configs() {
+        let mut words = Config::default().to_words();
+        words[SHIFT_BUTTON_WORD] = ERASED_WORD;
+        assert_eq!(
+            Config::from_words(&words).shift.button,
+            DEFAULT_SHIFT_BUTTON
+        );
+    }
+
+    #[test]
+    fn latching_round_trip() {
+        let mut config = Config {
+            latching: 1 << 2 | 1 << 31,
+            ..Config::default()
+        };
+        assert_eq!(round_trip(&config).latching, 1 << 2 | 1 << 31);
+
+        config.latching = 0;
+        assert_eq!(round_trip(&config).latching, 0);
+    }
+
+    #[test]
+    fn latching_defaults_for_older_configs() {
+        let mut words = Config::default().to_words();
+        words[LATCHING_WORD] = ERASED_WORD;
+        words[LATCHING_WORD + 1] = ERASED_WORD;
+        assert_eq!(Config::from_words(&words).latching, DEFAULT_LATCHING);
+    }
+
+    #[test]
+    fn key_bindings_round_trip() {
+        let mut config = Config::default();
+        config.key_bindings[0] = None;
+        config.key_bindings[3] = Some(KeyBinding {
+            trigger: Trigger::Button(7),
+            steps: [
+                Some(KeyCombo {
+                    modifiers: 0b101,
+                    key: Keyboard::G,
+                }),
+                None,
+                Some(KeyCombo {
+                    modifiers: 0,
+                    key: Keyboard::F10,
+                }),
+                None,
+            ],
+            step_duration: 80,
+        });
+        config.key_bindings[7] = Some(KeyBinding {
+            trigger: Trigger::Gesture {
+                button: 0,
+                gesture: Gesture::LongPress,
+            },
+            steps: [None; MACRO_STEPS],
+            step_duration: 0,
+        });
+        assert_eq!(round_trip(&config).key_bindings, config.key_bindings);
+
+        config.key_bindings = [None; MAX_KEY_BINDINGS];
+        assert_eq!(round_trip(&config).key_bindings, config.key_bindings);
+    }
+
+    #[test]
+    fn key_bindings_default_for_older_configs() {
+        let mut words = Config::default().to_words();
+        let bindings = KEY_BINDINGS_WORD..KEY_BINDINGS_WORD + MAX_KEY_BINDINGS * KEY_BINDING_WORDS;
+        words[bindings].fill(ERASED_WORD);
+        assert_eq!(
+            Config::from_words(&words).key_bindings,
+            DEFAULT_KEY_BINDINGS
+        );
+    }
+
+    #[test]
+    fn media_bindings_round_trip() {
+        let mut config = Config::default();
+        config.media_bindings[0] = None;
+        config.media_bindings[2] = Some(MediaBinding {
+            button: 31,
+            usage: MediaUsage::Consumer(Consumer::Mute),
+        });
+        config.media_bindings[7] = Some(MediaBinding {
+            button: 4,
+            usage: MediaUsage::Telephony(Telephony::HookSwitch),
+        });
+        assert_eq!(round_trip(&config).media_bindings, config.media_bindings);
+
+        config.media_bindings = [None; MAX_MEDIA_BINDINGS];
+        assert_eq!(round_trip(&config).media_bindings, config.media_bindings);
+    }
+
+    #[test]
+    fn media_bindings_default_for_older_configs() {
+        let mut words = Config::default().to_words();
+        let bindings =
+            MEDIA_BINDINGS_WORD..MEDIA_BINDINGS_WORD + MAX_MEDIA_BINDINGS * MEDIA_BINDING_WORDS;
+        words[bindings].fill(ERASED_WORD);
+        assert_eq!(
+            Config::from_words(&words).media_bindings,
+            DEFAULT_MEDIA_BINDINGS
+        );
+    }
+
+    #[test]
+    fn configs_of_another_axis_table_are_not_loaded() {
+        let mut config = Config::default();
+        config.calibration[0].min = 100;
+        let mut words = config.to_words();
+        words[1] += 1;
+        assert_eq!(Config::from_words(&words).calibration[0].min, 3300);
+    }
+
+    #[test]
+    fn legacy_calibration_is_kept() {
+        let mut words = [ERASED_WORD; CONFIG_WORDS];
+        words[..7].copy_from_slice(&[LEGACY_MAGIC, 10, 20, 30, 40, 50, 60]);
+        let config = Config::from_words(&words);
+        assert_eq!(
+            config
+                .calibration
+                .map(|calibration| (calibration.min, calibration.max)),
+            [(10, 20), (30, 40), (50, 60)]
+        );
+        assert_eq!(config.latching, DEFAULT_LATCHING);
+    }
+}
@@ -0,0 +1,134 @@
+//! Button edges captured by EXTI interrupts
+//!
+//! The main loop polls buttons between blocking ADC reads and flash writes, a page erase in
+//! [`crate::config::Config::save`] alone takes milliseconds. Edges of the reverse, calibrate and
+//! sync buttons are instead timestamped from interrupts into small queues, which [`Button`]
+//! drains, so button timing does not depend on main loop latency.
+//!
+//! [`Button`]: crate::button::Button
+use crate::button::SwitchInput;
+use crate::clock;
+use core::cell::RefCell;
+use core::sync::atomic::{AtomicBool, Ordering};
+use cortex_m::interrupt::Mutex;
+use heapless::Deque;
+use stm32f1xx_hal::afio;
+use stm32f1xx_hal::gpio::{Edge, ExtiPin, Input, PullUp, PA1, PB12, PB13};
+use stm32f1xx_hal::pac::{interrupt, Interrupt, EXTI, NVIC};
+
+/// Edges kept per button, the oldest ones are dropped when the main loop falls behind
+const QUEUE_LEN: usize = 8;
+
+/// Raw edges of one button as `(pressed, timestamp)`, plus the level after the last edge
+pub struct EdgeQueue {
+    edges: Mutex<RefCell<Deque<(bool, u32), QUEUE_LEN>>>,
+    pressed: AtomicBool,
+}
+
+impl EdgeQueue {
+    pub const fn new() -> Self {
+        Self {
+            edges: Mutex::new(RefCell::new(Deque::new())),
+            pressed: AtomicBool::new(false),
+        }
+    }
+
+    fn push(&self, pressed: bool, at: u32) {
+        self.pressed.store(pressed, Ordering::Relaxed);
+        cortex_m::interrupt::free(|cs| {
+            let mut edges = self.edges.borrow(cs).borrow_mut();
+            if edges.is_full() {
+                edges.pop_front();
+            }
+            edges.push_back((pressed, at)).ok();
+        });
+    }
+}
+
+impl SwitchInput for &'static EdgeQueue {
+    fn is_pressed(&self) -> bool {
+        self.pressed.load(Ordering::Relaxed)
+    }
+
+    fn pop_change(&mut self) -> Option<(bool, u32)> {
+        cortex_m::interrupt::free(|cs| self.edges.borrow(cs).borrow_mut().pop_front())
+    }
+}
+
+pub static REVERSE: EdgeQueue = EdgeQueue::new();
+pub static CALIBRATE: EdgeQueue = EdgeQueue::new();
+pub static SYNC: EdgeQueue = EdgeQueue::new();
+
+struct Pins {
+    reverse: PA1<Input<PullUp>>,
+    calibrate: PB12<Input<PullUp>>,
+    sync: PB13<Input<PullUp>>,
+}
+
+static PINS: Mutex<RefCell<Option<Pins>>> = Mutex::new(RefCell::new(None));
+
+/// Starts capturing edges of the button pins, they are read through [`REVERSE`],
+/// [`CALIBRATE`] and [`SYNC`] afterwards
+pub fn start(
+    reverse: PA1<Input<PullUp>>,
+    calibrate: PB12<Input<PullUp>>,
+    sync: PB13<Input<PullUp>>,
+    afio: &mut afio::Parts,
+    exti: &mut EXTI,
+) {
+    let mut pins = Pins {
+        reverse,
+        calibrate,
+        sync,
+    };
+    pins.reverse.make_interrupt_source(afio);
+    pins.reverse.trigger_on_edge(exti, Edge::RisingFalling);
+    pins.reverse.enable_interrupt(exti);
+    pins.calibrate.make_interrupt_source(afio);
+    pins.calibrate.trigger_on_edge(exti, Edge::RisingFalling);
+    pins.calibrate.enable_interrupt(exti);
+    pins.sync.make_interrupt_source(afio);
+    pins.sync.trigger_on_edge(exti, Edge::RisingFalling);
+    pins.sync.enable_interrupt(exti);
+
+    REVERSE
+        .pressed
+        .store(pins.reverse.is_low(), Ordering::Relaxed);
+    CALIBRATE
+        .pressed
+        .store(pins.calibrate.is_low(), Ordering::Relaxed);
+    SYNC.pressed.store(pins.sync.is_low(), Ordering::Relaxed);
+
+    cortex_m::interrupt::free(|cs| PINS.borrow(cs).replace(Some(pins)));
+    unsafe {
+        NVIC::unmask(Interrupt::EXTI1);
+        NVIC::unmask(Interrupt::EXTI15_10);
+    }
+}
+
+/// Records the edge of `pin` if it is the one which raised the interrupt
+fn capture<P: ExtiPin>(pin: &mut P, pressed: impl FnOnce(&P) -> bool, queue: &EdgeQueue) {
+    if pin.check_interrupt() {
+        pin.clear_interrupt_pending_bit();
+        queue.push(pressed(pin), clock::millis());
+    }
+}
+
+#[interrupt]
+fn EXTI1() {
+    cortex_m::interrupt::free(|cs| {
+        if let Some(pins) = PINS.borrow(cs).borrow_mut().as_mut() {
+            capture(&mut pins.reverse, |pin| pin.is_low(), &REVERSE);
+        }
+    });
+}
+
+#[interrupt]
+fn EXTI15_10() {
+    cortex_m::interrupt::free(|cs| {
+        if let Some(pins) = PINS.borrow(cs).borrow_mut().as_mut() {
+            capture(&mut pins.calibrate, |pin| pin.is_low(), &CALIBRATE);
+            capture(&mut pins.sync, |pin| pin.is_low(), &SYNC);
+        }
+    });
+}
@@ -0,0 +1,420 @@
+//! Quadrature rotary encoders, e.g. trim wheels and heading bug knobs
+//!
+//! Encoder pins are sampled from the TIM2 interrupt, independently of the main loop which may
+//! block on ADC reads or flash writes, so fast spinning does not lose steps. The interrupt only
+//! counts detents. The main loop turns them into button pulses or a virtual absolute axis, see
+//! [`EncoderOutput`], with acceleration applied to quickly following detents.
+use crate::hid::AXIS_MAX;
+use core::cell::RefCell;
+use core::sync::atomic::{AtomicI32, Ordering};
+use cortex_m::interrupt::Mutex;
+use stm32f1xx_hal::gpio::{ErasedPin, Input, PullUp};
+use stm32f1xx_hal::pac::{interrupt, Interrupt, NVIC, TIM2};
+use stm32f1xx_hal::prelude::*;
+use stm32f1xx_hal::rcc::Clocks;
+use stm32f1xx_hal::timer::{CounterHz, Event};
+use usbd_human_interface_device::page::Desktop;
+
+/// Pin sampling rate, each of the four quadrature states has to last at least one sample
+const SAMPLE_RATE_HZ: u32 = 4000;
+/// Milliseconds a detent pulse is reported for, and released for between pulses
+const PULSE_DURATION: u32 = 20;
+/// Detents queued for pulsing, further ones are dropped so the knob stops when the user does
+const MAX_PENDING_PULSES: i32 = 8;
+/// Detents closer than this, in milliseconds, are multiplied by [`FAST_MULTIPLIER`]
+const FAST_INTERVAL: u32 = 40;
+const FAST_MULTIPLIER: u16 = 4;
+/// Detents closer than this, in milliseconds, are multiplied by [`MEDIUM_MULTIPLIER`]
+const MEDIUM_INTERVAL: u32 = 100;
+const MEDIUM_MULTIPLIER: u16 = 2;
+
+#[derive(Clone, Copy)]
+pub enum EncoderOutput {
+    /// Every detent pulses one of two logical buttons
+    Buttons { increment: u8, decrement: u8 },
+    /// Detents move an axis reported after the parameter axis, starting at center
+    Axis {
+        usage: Desktop,
+        /// Report units per detent, before acceleration
+        step: u16,
+    },
+}
+
+pub struct EncoderDefinition {
+    pub output: EncoderOutput,
+}
+
+/// Encoders in the order their pins are passed to [`start`]
+pub const ENCODERS: [EncoderDefinition; 2] = [
+    // elevator trim wheel, PA8 and PA9
+    EncoderDefinition {
+        output: EncoderOutput::Axis {
+            usage: Desktop::Dial,
+            step: 8,
+        },
+    },
+    // heading bug knob, PA10 and PC14
+    EncoderDefinition {
+        output: EncoderOutput::Buttons {
+            increment: 17,
+            decrement: 18,
+        },
+    },
+];
+
+pub const ENCODERS_COUNT: usize = ENCODERS.len();
+
+const fn encoder_axes() -> usize {
+    let mut count = 0;
+    let mut i = 0;
+    while i < ENCODERS_COUNT {
+        if let EncoderOutput::Axis { .. } = ENCODERS[i].output {
+            count += 1;
+        }
+        i += 1;
+    }
+    count
+}
+
+/// Encoders reported as axes
+pub const ENCODER_AXES: usize = encoder_axes();
+
+/// Usages of encoders reported as axes, in [`ENCODERS`] order
+pub const ENCODER_AXIS_USAGES: [Desktop; ENCODER_AXES] = encoder_axis_usages();
+
+const fn encoder_axis_usages() -> [Desktop; ENCODER_AXES] {
+    let mut usages = [Desktop::Undefined; ENCODER_AXES];
+    let mut at = 0;
+    let mut i = 0;
+    while i < ENCODERS_COUNT {
+        if let EncoderOutput::Axis { usage, .. } = ENCODERS[i].output {
+            usages[at] = usage;
+            at += 1;
+        }
+        i += 1;
+    }
+    usages
+}
+
+/// Decodes quadrature states into detents. Detents rest with both pins high
+#[derive(Clone, Copy)]
+pub struct Quadrature {
+    state: u8,
+    /// Quarter steps since the last rest state
+    steps: i8,
+}
+
+impl Quadrature {
+    pub const fn new() -> Self {
+        Self {
+            state: 0b11,
+            steps: 0,
+        }
+    }
+
+    /// Takes pin levels, returns `1` or `-1` when a detent is completed
+    pub fn update(&mut self, a: bool, b: bool) -> i32 {
+        #[rustfmt::skip]
+        const STEPS: [i8; 16] = [
+            0, -1, 1, 0,
+            1, 0, 0, -1,
+            -1, 0, 0, 1,
+            0, 1, -1, 0,
+        ];
+
+        let state = u8::from(a) << 1 | u8::from(b);
+        self.steps += STEPS[usize::from(self.state << 2 | state)];
+        self.state = state;
+        if state != 0b11 {
+            return 0;
+        }
+
+        // bounces cancel out, a full detent is four quarter steps
+        let steps = core::mem::take(&mut self.steps);
+        if steps >= 2 {
+            1
+        } else if steps <= -2 {
+            -1
+        } else {
+            0
+        }
+    }
+}
+
+type EncoderPins = (ErasedPin<Input<PullUp>>, ErasedPin<Input<PullUp>>);
+
+struct Sampler {
+    timer: CounterHz<TIM2>,
+    pins: [EncoderPins; ENCODERS_COUNT],
+    decoders: [Quadrature; ENCODERS_COUNT],
+}
+
+static SAMPLER: Mutex<RefCell<Option<Sampler>>> = Mutex::new(RefCell::new(None));
+static DETENTS: [AtomicI32; ENCODERS_COUNT] = [const { AtomicI32::new(0) }; ENCODERS_COUNT];
+
+/// Starts sampling encoder pins, `(A, B)` pairs in [`ENCODERS`] order
+pub fn start(tim: TIM2, pins: [EncoderPins; ENCODERS_COUNT], clocks: &Clocks) {
+    let mut timer = tim.counter_hz(clocks);
+    timer.start(SAMPLE_RATE_HZ.Hz()).unwrap();
+    timer.listen(Event::Update);
+
+    cortex_m::interrupt::free(|cs| {
+        SAMPLER.borrow(cs).replace(Some(Sampler {
+            timer,
+            pins,
+            decoders: [Quadrature::new(); ENCODERS_COUNT],
+        }));
+    });
+    unsafe { NVIC::unmask(Interrupt::TIM2) };
+}
+
+/// Detents of every encoder since the last call, positive clockwise
+pub fn take_detents() -> [i32; ENCODERS_COUNT] {
+    core::array::from_fn(|i| DETENTS[i].swap(0, Ordering::Relaxed))
+}
+
+#[interrupt]
+fn TIM2() {
+    cortex_m::interrupt::free(|cs| {
+        let mut sampler = SAMPLER.borrow(cs).borrow_mut();
+        let Some(sampler) = sampler.as_mut() else {
+            return;
+        };
+        sampler.timer.clear_interrupt(Event::Update);
+
+        for (i, (a, b)) in sampler.pins.iter().enumerate() {
+            let detent = sampler.decoders[i].update(a.is_high(), b.is_high());
+            if detent != 0 {
+                DETENTS[i].fetch_add(detent, Ordering::Relaxed);
+            }
+        }
+    });
+}
+
+/// Main loop side of an encoder: acceleration, pulses and axis value
+pub struct Encoder {
+    output: EncoderOutput,
+    /// Axis value when reported as an axis
+    value: u16,
+    last_detent: u32,
+    /// Detents waiting to be pulsed, positive ones increment
+    pending: i32,
+    /// Logical button currently pulsing and when its pulse started
+    pulse: Option<(u8, u32)>,
+}
+
+impl Encoder {
+    pub fn new(definition: &EncoderDefinition) -> Self {
+        Self {
+            output: definition.output,
+            value: AXIS_MAX / 2,
+            last_detent: 0,
+            pending: 0,
+            pulse: None,
+        }
+    }
+
+    fn multiplier(&mut self, now: u32) -> u16 {
+        let interval = now.wrapping_sub(self.last_detent);
+        self.last_detent = now;
+        if interval < FAST_INTERVAL {
+            FAST_MULTIPLIER
+        } else if interval < MEDIUM_INTERVAL {
+            MEDIUM_MULTIPLIER
+        } else {
+            1
+        }
+    }
+
+    /// Takes detents from [`take_detents`], returns logical buttons held by a pulse
+    pub fn update(&mut self, detents: i32, now: u32) -> u32 {
+        match self.output {
+            EncoderOutput::Axis { step, .. } => {
+                if detents != 0 {
+                    // a stalled main loop may hand over more detents than the whole axis range
+                    let count = detents.unsigned_abs().min(u32::from(AXIS_MAX)) as u16;
+                    let delta = step
+                        .saturating_mul(self.multiplier(now))
+                        .saturating_mul(count);
+                    self.value = if detents > 0 {
+                        self.value.saturating_add(delta).min(AXIS_MAX)
+                    } else {
+                        self.value.saturating_sub(delta)
+                    };
+                }
+                0
+            }
+            EncoderOutput::Buttons {
+                increment,
+                decrement,
+            } => {
+                if detents != 0 {
+                    let detents = detents.clamp(-MAX_PENDING_PULSES, MAX_PENDING_PULSES)
+                        * i32::from(self.multiplier(now));
+                    self.pending =
+                        (self.pending + detents).clamp(-MAX_PENDING_PULSES, MAX_PENDING_PULSES);
+                }
+
+                match self.pulse {
+                    Some((button, started)) => {
+                        let elapsed = now.wrapping_sub(started);
+                        if elapsed < PULSE_DURATION {
+                            return 1 << button;
+                        }
+                        if elapsed >= PULSE_DURATION * 2 {
+                            self.pulse = None;
+                        }
+                    }
+                    None if self.pending != 0 => {
+                        let button = if self.pending > 0 {
+                            increment
+                        } else {
+                            decrement
+                        };
+                        self.pending -= self.pending.signum();
+                        self.pulse = Some((button, now));
+                        return 1 << button;
+                    }
+                    None => {}
+                }
+                0
+            }
+        }
+    }
+}
+
+/// Values of encoders reported as axes, for [`crate::hid::JoystickReport::encoder_axes`]
+pub fn axis_values(encoders: &[Encoder; ENCODERS_COUNT]) -> [u16; ENCODER_AXES] {
+    let mut values = [0; ENCODER_AXES];
+    let axes = encoders
+        .iter()
+        .filter(|encoder| matches!(encoder.output, EncoderOutput::Axis { .. }));
+    for (value, encoder) in values.iter_mut().zip(axes) {
+        *value = encoder.value;
+    }
+    values
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const AXIS: EncoderDefinition = EncoderDefinition {
+        output: EncoderOutput::Axis {
+            usage: Desktop::Dial,
+            step: 8,
+        },
+    };
+    const BUTTONS: EncoderDefinition = EncoderDefinition {
+        output: EncoderOutput::Buttons {
+            increment: 3,
+            decrement: 4,
+        },
+    };
+
+    /// Detents reported for a sequence of `(A, B)` pin levels
+    fn decode(quadrature: &mut Quadrature, states: &[(bool, bool)]) -> Vec<i32> {
+        states
+            .iter()
+            .map(|&(a, b)| quadrature.update(a, b))
+            .collect()
+    }
+
+    /// Pulses started by `encoder` while updating it every 5 ms from `from` until `to`
+    fn pulses(encoder: &mut Encoder, button: u8, from: u32, to: u32) -> usize {
+        let mut held = false;
+        let mut pulses = 0;
+        for now in (from..to).step_by(5) {
+            let pressed = encoder.update(0, now) & 1 << button != 0;
+            if pressed && !held {
+                pulses += 1;
+            }
+            held = pressed;
+        }
+        pulses
+    }
+
+    #[test]
+    fn quadrature_detents() {
+        let clockwise = [(false, true), (false, false), (true, false), (true, true)];
+        let counterclockwise = [(true, false), (false, false), (false, true), (true, true)];
+        let mut quadrature = Quadrature::new();
+        assert_eq!(decode(&mut quadrature, &clockwise), [0, 0, 0, 1]);
+        assert_eq!(decode(&mut quadrature, &counterclockwise), [0, 0, 0, -1]);
+    }
+
+    #[test]
+    fn quadrature_ignores_bounces_and_half_detents() {
+        let mut quadrature = Quadrature::new();
+        // contact bounce back to rest
+        assert_eq!(
+            decode(&mut quadrature, &[(false, true), (true, true)]),
+            [0, 0]
+        );
+        // turned half way and back
+        let half = [(false, true), (false, false), (false, true), (true, true)];
+        assert_eq!(decode(&mut quadrature, &half), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn quadrature_ignores_invalid_transitions() {
+        let mut quadrature = Quadrature::new();
+        // both pins changing at once is a missed sample, it does not count as a step
+        assert_eq!(
+            decode(&mut quadrature, &[(false, false), (true, true)]),
+            [0, 0]
+        );
+        let clockwise = [(false, true), (false, false), (true, false), (true, true)];
+        assert_eq!(decode(&mut quadrature, &clockwise), [0, 0, 0, 1]);
+    }
+
+    #[test]
+    fn axis_accelerates_quick_detents() {
+        let mut encoder = Encoder::new(&AXIS);
+        let center = AXIS_MAX / 2;
+        encoder.update(1, 1000);
+        assert_eq!(encoder.value, center + 8);
+        encoder.update(1, 1000 + MEDIUM_INTERVAL - 1);
+        assert_eq!(encoder.value, center + 8 + 16);
+        encoder.update(-1, 1000 + MEDIUM_INTERVAL);
+        assert_eq!(encoder.value, center + 8 + 16 - 32);
+        encoder.update(-1, 2000);
+        assert_eq!(encoder.value, center - 16);
+    }
+
+    #[test]
+    fn axis_saturates_on_piled_up_detents() {
+        let mut encoder = Encoder::new(&AXIS);
+        encoder.update(i32::MAX, 1000);
+        assert_eq!(encoder.value, AXIS_MAX);
+        encoder.update(i32::MIN, 1010);
+        assert_eq!(encoder.value, 0);
+    }
+
+    #[test]
+    fn buttons_pulse_per_detent() {
+        let mut encoder = Encoder::new(&BUTTONS);
+        assert_eq!(encoder.update(2, 1000), 1 << 3);
+        assert_eq!(encoder.update(0, 1000 + PULSE_DURATION - 1), 1 << 3);
+        assert_eq!(encoder.update(0, 1000 + PULSE_DURATION), 0);
+        assert_eq!(pulses(&mut encoder, 3, 1000 + PULSE_DURATION, 2000), 1);
+
+        encoder.update(-1, 3000);
+        assert_eq!(pulses(&mut encoder, 4, 3000, 4000), 1);
+    }
+
+    #[test]
+    fn pending_pulses_are_clamped() {
+        let mut encoder = Encoder::new(&BUTTONS);
+        encoder.update(i32::MAX, 1000);
+        assert_eq!(
+            pulses(&mut encoder, 3, 1005, 5000),
+            MAX_PENDING_PULSES as usize
+        );
+        encoder.update(i32::MIN, 6000);
+        assert_eq!(
+            pulses(&mut encoder, 4, 6005, 10_000),
+            MAX_PENDING_PULSES as usize
+        );
+    }
+}
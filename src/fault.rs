@@ -0,0 +1,149 @@
+//! Detection of disconnected or shorted potentiometers
+//!
+//! A broken wire pins the ADC reading to one of the rails, which would slam the axis to the end
+//! of its travel. Readings pinned at a rail past the calibrated range, or far outside of it, are
+//! not passed to the axis so it stays at its last good value. If they persist the potentiometer
+//! is reported as faulty. A lever at its end stop may read slightly past the calibrated range,
+//! which is not a fault.
+use crate::config::AxisCalibration;
+
+/// Largest value of 12 bit ADC
+const ADC_MAX: u16 = 4095;
+/// Readings at a rail this far past the calibrated range are considered pinned
+const RAIL_MARGIN: u16 = 16;
+/// Readings further than this outside of the calibrated range are never valid
+const OUT_OF_RANGE: u16 = 256;
+/// Milliseconds of bad readings after which the potentiometer is faulty
+const FAULT_MS: u32 = 500;
+/// Milliseconds of good readings after which the fault is cleared
+const RECOVERY_MS: u32 = 200;
+
+/// HID button reported while any potentiometer is faulty, the last unshifted one
+pub const FAULT_BUTTON: u64 = 1 << 31;
+
+#[derive(Clone, Copy, Default)]
+pub struct PotMonitor {
+    /// Time of the first bad reading in a row
+    bad_since: Option<u32>,
+    /// Time of the first good reading in a row
+    good_since: Option<u32>,
+    faulty: bool,
+}
+
+impl PotMonitor {
+    pub const fn new() -> Self {
+        Self {
+            bad_since: None,
+            good_since: None,
+            faulty: false,
+        }
+    }
+
+    /// Returns `true` if the reading can be passed to the axis, `now` is [`crate::clock::millis`]
+    pub fn check(&mut self, reading: u16, calibration: &AxisCalibration, now: u32) -> bool {
+        let past_range = |margin: u16| {
+            reading.saturating_add(margin) < calibration.min
+                || reading > calibration.max.saturating_add(margin)
+        };
+        let at_rail = reading == 0 || reading == ADC_MAX;
+
+        if (at_rail && past_range(RAIL_MARGIN)) || past_range(OUT_OF_RANGE) {
+            self.good_since = None;
+            let since = *self.bad_since.get_or_insert(now);
+            if now.wrapping_sub(since) >= FAULT_MS {
+                self.faulty = true;
+            }
+            false
+        } else {
+            self.bad_since = None;
+            let since = *self.good_since.get_or_insert(now);
+            if now.wrapping_sub(since) >= RECOVERY_MS {
+                self.faulty = false;
+            }
+            !self.faulty
+        }
+    }
+
+    pub fn faulty(&self) -> bool {
+        self.faulty
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const CALIBRATION: AxisCalibration = AxisCalibration {
+        min: 1000,
+        max: 3000,
+    };
+
+    /// Checks `reading` every 10 ms from `from` until `to`, returns the result of the last check
+    fn hold(monitor: &mut PotMonitor, reading: u16, from: u32, to: u32) -> bool {
+        let mut passed = false;
+        for now in (from..=to).step_by(10) {
+            passed = monitor.check(reading, &CALIBRATION, now);
+        }
+        passed
+    }
+
+    #[test]
+    fn readings_in_range_pass() {
+        let mut monitor = PotMonitor::new();
+        assert!(hold(&mut monitor, 2000, 0, 1000));
+        assert!(!monitor.faulty());
+    }
+
+    #[test]
+    fn end_stop_past_calibration_is_not_a_fault() {
+        let calibration = AxisCalibration::default();
+        let mut monitor = PotMonitor::new();
+        for (i, reading) in [4080, 4090, 4095, 4093].into_iter().enumerate() {
+            assert!(monitor.check(reading, &calibration, i as u32 * 400));
+        }
+        assert!(!monitor.faulty());
+    }
+
+    #[test]
+    fn pinned_rail_becomes_faulty_after_timeout() {
+        let mut monitor = PotMonitor::new();
+        assert!(!hold(&mut monitor, ADC_MAX, 0, FAULT_MS - 10));
+        assert!(!monitor.faulty());
+        assert!(!monitor.check(ADC_MAX, &CALIBRATION, FAULT_MS));
+        assert!(monitor.faulty());
+    }
+
+    #[test]
+    fn far_out_of_range_becomes_faulty() {
+        let mut monitor = PotMonitor::new();
+        assert!(!hold(
+            &mut monitor,
+            CALIBRATION.max + OUT_OF_RANGE + 1,
+            0,
+            FAULT_MS
+        ));
+        assert!(monitor.faulty());
+    }
+
+    #[test]
+    fn short_glitch_is_not_a_fault() {
+        let mut monitor = PotMonitor::new();
+        assert!(!hold(&mut monitor, 0, 0, FAULT_MS - 100));
+        assert!(monitor.check(2000, &CALIBRATION, FAULT_MS - 90));
+        assert!(!hold(&mut monitor, 0, FAULT_MS - 80, FAULT_MS + 300));
+        assert!(!monitor.faulty());
+    }
+
+    #[test]
+    fn recovers_after_good_readings() {
+        let mut monitor = PotMonitor::new();
+        hold(&mut monitor, 0, 0, FAULT_MS);
+        assert!(monitor.faulty());
+
+        // still frozen until the readings are good for long enough
+        assert!(!hold(&mut monitor, 2000, 1000, 1000 + RECOVERY_MS - 10));
+        assert!(monitor.faulty());
+        assert!(monitor.check(2000, &CALIBRATION, 1000 + RECOVERY_MS));
+        assert!(!monitor.faulty());
+    }
+}
@@ -0,0 +1,215 @@
+//! Glitch rejection for raw ADC readings
+//!
+//! Worn potentiometers produce single sample spikes to the rails, which smoothing spreads into
+//! visible jumps. These filters are effects of the axis chain, see [`ChainEffect`], and run on
+//! the raw reading before the effects of the `axis` crate.
+use axis::{Axis, DynEffect};
+
+/// Largest window of [`Median`]
+pub const MAX_MEDIAN_WINDOW: usize = 9;
+
+#[derive(Clone)]
+pub enum GlitchFilter {
+    /// Readings are passed through as is
+    None,
+    Median(Median),
+    SlewReject(SlewReject),
+}
+
+impl GlitchFilter {
+    pub fn apply(&mut self, value: u16) -> u16 {
+        match self {
+            Self::None => value,
+            Self::Median(filter) => filter.apply(value),
+            Self::SlewReject(filter) => filter.apply(value),
+        }
+    }
+}
+
+impl From<Median> for GlitchFilter {
+    fn from(filter: Median) -> Self {
+        Self::Median(filter)
+    }
+}
+
+impl From<SlewReject> for GlitchFilter {
+    fn from(filter: SlewReject) -> Self {
+        Self::SlewReject(filter)
+    }
+}
+
+/// Median of the last `window` readings. Spikes shorter than half of the window are discarded
+#[derive(Clone)]
+pub struct Median {
+    samples: [u16; MAX_MEDIAN_WINDOW],
+    window: usize,
+    next: usize,
+    filled: usize,
+}
+
+impl Median {
+    pub const fn new(window: usize) -> Self {
+        assert!(window > 0 && window <= MAX_MEDIAN_WINDOW);
+        Self {
+            samples: [0; MAX_MEDIAN_WINDOW],
+            window,
+            next: 0,
+            filled: 0,
+        }
+    }
+
+    pub fn apply(&mut self, value: u16) -> u16 {
+        self.samples[self.next] = value;
+        self.next = (self.next + 1) % self.window;
+        self.filled = (self.filled + 1).min(self.window);
+
+        let mut sorted = self.samples;
+        let sorted = &mut sorted[..self.filled];
+        sorted.sort_unstable();
+        sorted[self.filled / 2]
+    }
+}
+
+/// Rejects readings which step further than `max_step` from the last accepted one. A step is
+/// accepted once it is confirmed by `confirm` consecutive readings, so fast lever movement
+/// is only delayed
+#[derive(Clone)]
+pub struct SlewReject {
+    max_step: u16,
+    confirm: u8,
+    last: Option<u16>,
+    outliers: u8,
+}
+
+impl SlewReject {
+    pub const fn new(max_step: u16, confirm: u8) -> Self {
+        Self {
+            max_step,
+            confirm,
+            last: None,
+            outliers: 0,
+        }
+    }
+
+    pub fn apply(&mut self, value: u16) -> u16 {
+        match self.last {
+            Some(last) if value.abs_diff(last) > self.max_step => {
+                self.outliers += 1;
+                if self.outliers < self.confirm {
+                    return last;
+                }
+            }
+            _ => {}
+        }
+
+        self.outliers = 0;
+        self.last = Some(value);
+        value
+    }
+}
+
+/// Effect of an axis chain, [`GlitchFilter`]s or the [`DynEffect`]s of the `axis` crate
+#[derive(Clone)]
+pub enum ChainEffect {
+    Glitch(GlitchFilter),
+    Axis(DynEffect),
+}
+
+impl From<GlitchFilter> for ChainEffect {
+    fn from(filter: GlitchFilter) -> Self {
+        Self::Glitch(filter)
+    }
+}
+
+impl From<DynEffect> for ChainEffect {
+    fn from(effect: DynEffect) -> Self {
+        Self::Axis(effect)
+    }
+}
+
+/// Feeds `reading` through `chain` into `axis`. Glitch filters see the raw reading and run
+/// first, the axis applies its own effects in chain order
+pub fn update(axis: &mut Axis, reading: u16, chain: &mut [ChainEffect]) {
+    let value = chain
+        .iter_mut()
+        .fold(reading, |value, effect| match effect {
+            ChainEffect::Glitch(filter) => filter.apply(value),
+            ChainEffect::Axis(_) => value,
+        });
+    axis.update(
+        value,
+        chain.iter_mut().filter_map(|effect| match effect {
+            ChainEffect::Axis(effect) => Some(effect),
+            ChainEffect::Glitch(_) => None,
+        }),
+    );
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Output of `filter` for each of `readings`
+    fn run<const N: usize>(filter: &mut GlitchFilter, readings: [u16; N]) -> [u16; N] {
+        readings.map(|reading| filter.apply(reading))
+    }
+
+    #[test]
+    fn median_fills_window_on_startup() {
+        let mut filter = GlitchFilter::from(Median::new(5));
+        // the median of the readings so far, the empty part of the window is not zeros
+        assert_eq!(run(&mut filter, [2000, 2010, 1990]), [2000, 2010, 2000]);
+    }
+
+    #[test]
+    fn median_rejects_spikes() {
+        let mut filter = GlitchFilter::from(Median::new(5));
+        run(&mut filter, [2000; 5]);
+        assert_eq!(
+            run(&mut filter, [4095, 2000, 0, 4095, 2000, 2000]),
+            [2000; 6]
+        );
+    }
+
+    #[test]
+    fn median_follows_steps() {
+        let mut filter = GlitchFilter::from(Median::new(5));
+        run(&mut filter, [2000; 5]);
+        assert_eq!(
+            run(&mut filter, [3000, 3000, 3000, 3000]),
+            [2000, 2000, 3000, 3000]
+        );
+    }
+
+    #[test]
+    fn slew_reject_accepts_first_reading() {
+        let mut filter = GlitchFilter::from(SlewReject::new(200, 3));
+        assert_eq!(run(&mut filter, [4000, 4100]), [4000, 4100]);
+    }
+
+    #[test]
+    fn slew_reject_rejects_spikes() {
+        let mut filter = GlitchFilter::from(SlewReject::new(200, 3));
+        run(&mut filter, [2000]);
+        assert_eq!(
+            run(&mut filter, [4095, 2050, 0, 0, 2100]),
+            [2000, 2050, 2050, 2050, 2100]
+        );
+    }
+
+    #[test]
+    fn slew_reject_follows_confirmed_steps() {
+        let mut filter = GlitchFilter::from(SlewReject::new(200, 3));
+        run(&mut filter, [2000]);
+        assert_eq!(
+            run(&mut filter, [3000, 3010, 3020, 3030]),
+            [2000, 2000, 3020, 3030]
+        );
+    }
+
+    #[test]
+    fn none_passes_readings() {
+        let mut filter = GlitchFilter::None;
+        assert_eq!(run(&mut filter, [0, 4095, 10]), [0, 4095, 10]);
+    }
+}
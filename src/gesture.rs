@@ -0,0 +1,236 @@
+//! Button gestures: long press, double and triple click and multi-button chords
+//!
+//! Gestures are detected on top of the debounced [`Button`] state. Time is passed in by the
+//! caller as a monotonic millisecond timestamp, so detection does not depend on any timer
+//! peripheral.
+use crate::button::{Button, SwitchInput};
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Gesture {
+    /// Released before the long press duration, and not followed by another click
+    Click,
+    DoubleClick,
+    TripleClick,
+    /// Held for the long press duration. Emitted once, while the button is still held
+    LongPress,
+    /// All buttons of a [`Chord`] became held
+    Chord,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct GestureTiming {
+    /// Milliseconds a button has to be held to emit [`Gesture::LongPress`]
+    pub long_press: u32,
+    /// Maximum milliseconds between a release and the next press of a multi click
+    pub multi_click: u32,
+}
+
+pub struct GestureButton<P> {
+    button: Button<P>,
+    timing: GestureTiming,
+    held: bool,
+    pressed_at: u32,
+    released_at: u32,
+    clicks: u8,
+    /// Current press already produced a gesture or was cancelled, its release is ignored
+    consumed: bool,
+}
+
+impl<P: SwitchInput> GestureButton<P> {
+    pub fn new(button: Button<P>, timing: GestureTiming) -> Self {
+        Self {
+            button,
+            timing,
+            held: false,
+            pressed_at: 0,
+            released_at: 0,
+            clicks: 0,
+            consumed: false,
+        }
+    }
+
+    /// Debounced state of the button as of the last [`GestureButton::update`]
+    pub fn held(&self) -> bool {
+        self.held
+    }
+
+    /// Drops pending clicks and ignores the current press, e.g. when it became part of a chord
+    pub fn cancel(&mut self) {
+        self.clicks = 0;
+        self.consumed = self.held;
+    }
+
+    pub fn update(&mut self, now: u32) -> Option<Gesture> {
+        let held = self.button.toggle(now);
+        let pressed = held && !self.held;
+        let released = !held && self.held;
+        self.held = held;
+
+        if pressed {
+            self.pressed_at = now;
+            self.consumed = false;
+        }
+
+        if held {
+            if !self.consumed && now.wrapping_sub(self.pressed_at) >= self.timing.long_press {
+                self.consumed = true;
+                self.clicks = 0;
+                return Some(Gesture::LongPress);
+            }
+            return None;
+        }
+
+        if released && !self.consumed {
+            self.clicks += 1;
+            self.released_at = now;
+            if self.clicks == 3 {
+                self.clicks = 0;
+                return Some(Gesture::TripleClick);
+            }
+        }
+
+        if self.clicks > 0 && now.wrapping_sub(self.released_at) >= self.timing.multi_click {
+            let clicks = core::mem::take(&mut self.clicks);
+            return Some(if clicks == 1 {
+                Gesture::Click
+            } else {
+                Gesture::DoubleClick
+            });
+        }
+
+        None
+    }
+}
+
+/// Buttons held together, identified by a bitmask of button indices
+pub struct Chord {
+    buttons: u32,
+    active: bool,
+}
+
+impl Chord {
+    pub const fn new(buttons: u32) -> Self {
+        Self {
+            buttons,
+            active: false,
+        }
+    }
+
+    /// Takes a bitmask of currently held buttons and returns [`Gesture::Chord`] once all buttons
+    /// of the chord become held
+    pub fn update(&mut self, held: u32) -> Option<Gesture> {
+        let active = held & self.buttons == self.buttons;
+        let fired = active && !self.active;
+        self.active = active;
+        fired.then_some(Gesture::Chord)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use core::cell::Cell;
+    use core::convert::Infallible;
+    use embedded_hal::digital::v2::InputPin;
+
+    const TIMING: GestureTiming = GestureTiming {
+        long_press: 800,
+        multi_click: 300,
+    };
+
+    /// Switch to ground with a pull-up, low while pressed
+    struct FakePin<'a>(&'a Cell<bool>);
+
+    impl InputPin for FakePin<'_> {
+        type Error = Infallible;
+
+        fn is_high(&self) -> Result<bool, Infallible> {
+            Ok(!self.0.get())
+        }
+
+        fn is_low(&self) -> Result<bool, Infallible> {
+            Ok(self.0.get())
+        }
+    }
+
+    /// Sets the switch at every `(time, pressed)` and updates the button at every millisecond
+    /// until `until`, returning the gestures with their timestamps
+    fn play(
+        button: &mut GestureButton<FakePin>,
+        pressed: &Cell<bool>,
+        changes: &[(u32, bool)],
+        until: u32,
+    ) -> Vec<(u32, Gesture)> {
+        let mut gestures = Vec::new();
+        for now in 0..=until {
+            if let Some(&(_, state)) = changes.iter().find(|(at, _)| *at == now) {
+                pressed.set(state);
+            }
+            if let Some(gesture) = button.update(now) {
+                gestures.push((now, gesture));
+            }
+        }
+        gestures
+    }
+
+    #[test]
+    fn click_after_multi_click_window() {
+        let pressed = Cell::new(false);
+        let mut button = GestureButton::new(Button::new(FakePin(&pressed)), TIMING);
+        let gestures = play(&mut button, &pressed, &[(100, true), (200, false)], 1000);
+        assert_eq!(gestures, [(500, Gesture::Click)]);
+    }
+
+    #[test]
+    fn double_and_triple_click() {
+        let pressed = Cell::new(false);
+        let mut button = GestureButton::new(Button::new(FakePin(&pressed)), TIMING);
+        let double = [(100, true), (150, false), (250, true), (300, false)];
+        assert_eq!(
+            play(&mut button, &pressed, &double, 1000),
+            [(600, Gesture::DoubleClick)]
+        );
+
+        let pressed = Cell::new(false);
+        let mut button = GestureButton::new(Button::new(FakePin(&pressed)), TIMING);
+        let triple = [
+            (100, true),
+            (150, false),
+            (250, true),
+            (300, false),
+            (400, true),
+            (450, false),
+        ];
+        assert_eq!(
+            play(&mut button, &pressed, &triple, 1000),
+            [(450, Gesture::TripleClick)]
+        );
+    }
+
+    #[test]
+    fn long_press_while_held_without_click_on_release() {
+        let pressed = Cell::new(false);
+        let mut button = GestureButton::new(Button::new(FakePin(&pressed)), TIMING);
+        let gestures = play(&mut button, &pressed, &[(100, true), (1500, false)], 2500);
+        assert_eq!(gestures, [(900, Gesture::LongPress)]);
+    }
+
+    #[test]
+    fn cancelled_press_is_ignored() {
+        let pressed = Cell::new(false);
+        let mut button = GestureButton::new(Button::new(FakePin(&pressed)), TIMING);
+        assert_eq!(play(&mut button, &pressed, &[(100, true)], 150), []);
+        button.cancel();
+        assert_eq!(play(&mut button, &pressed, &[(200, false)], 1500), []);
+    }
+
+    #[test]
+    fn chord_fires_once_all_buttons_are_held() {
+        let mut chord = Chord::new(0b11);
+        assert_eq!(chord.update(0b01), None);
+        assert_eq!(chord.update(0b11), Some(Gesture::Chord));
+        assert_eq!(chord.update(0b111), None);
+        assert_eq!(chord.update(0b10), None);
+        assert_eq!(chord.update(0b11), Some(Gesture::Chord));
+    }
+}
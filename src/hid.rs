@@ -0,0 +1,261 @@
+//! Joystick device sized after the [`AXES`] table
+use crate::axes::{AXES, AXES_COUNT, FLIGHT_SIMULATION_DEVICE, PARAMETER_AXIS_USAGE};
+use crate::encoder::{ENCODER_AXES, ENCODER_AXIS_USAGES};
+use stm32f1xx_hal::prelude::*;
+use usb_device::class_prelude::{UsbBus, UsbBusAllocator};
+use usb_device::UsbError;
+use usbd_human_interface_device::usb_class::prelude::*;
+
+/// Axes are reported in `0..=AXIS_MAX` range
+pub const AXIS_MAX: u16 = 1024;
+
+/// Buttons in the report, one bit each
+pub const BUTTONS: usize = 64;
+/// Logical button of the reverse button
+pub const REVERSE_BUTTON: u32 = 1 << 0;
+/// Logical button of the first button matrix key, keys follow in matrix order
+pub const MATRIX_FIRST_BUTTON: usize = 1;
+
+/// Axes in the report: [`AXES`], the parameter axis and encoders reported as axes
+const REPORT_AXES: usize = AXES_COUNT + 1 + ENCODER_AXES;
+
+#[rustfmt::skip]
+const JOYSTICK_HEADER: &[u8] = &[
+    0x05, 0x01, // Usage Page (Generic Desktop)
+    0x09, 0x04, // Usage (Joystick)
+    0xa1, 0x01, // Collection (Application)
+    0x09, 0x01, //   Usage (Pointer)
+    0xa1, 0x00, //   Collection (Physical)
+];
+
+#[rustfmt::skip]
+const FLIGHT_SIMULATION_HEADER: &[u8] = &[
+    0x05, 0x02, // Usage Page (Simulation Controls)
+    0x09, 0x01, // Usage (Flight Simulation Device)
+    0xa1, 0x01, // Collection (Application)
+    0x05, 0x01, //   Usage Page (Generic Desktop)
+    0x09, 0x01, //   Usage (Pointer)
+    0xa1, 0x00, //   Collection (Physical)
+];
+
+const DESCRIPTOR_HEADER: &[u8] = if FLIGHT_SIMULATION_DEVICE {
+    FLIGHT_SIMULATION_HEADER
+} else {
+    JOYSTICK_HEADER
+};
+
+#[rustfmt::skip]
+const DESCRIPTOR_AXES: &[u8] = &[
+    0x15, 0x00,                                            // Logical Minimum (0)
+    0x26, AXIS_MAX.to_le_bytes()[0], AXIS_MAX.to_le_bytes()[1], // Logical Maximum (AXIS_MAX)
+    0x75, 0x10,                                            // Report Size (16)
+    0x95, REPORT_AXES as u8,                               // Report Count (REPORT_AXES)
+    0x81, 0x02,                                            // Input (Data, Variable, Absolute)
+    0xc0,                                                  // End Collection
+];
+
+#[rustfmt::skip]
+const DESCRIPTOR_BUTTONS: &[u8] = &[
+    0x05, 0x09,          //   Usage Page (Button)
+    0x19, 0x01,          //   Usage Minimum (1)
+    0x29, BUTTONS as u8, //   Usage Maximum (BUTTONS)
+    0x15, 0x00,          //   Logical Minimum (0)
+    0x25, 0x01,          //   Logical Maximum (1)
+    0x75, 0x01,          //   Report Size (1)
+    0x95, BUTTONS as u8, //   Report Count (BUTTONS)
+    0x81, 0x02,          //   Input (Data, Variable, Absolute)
+];
+
+#[rustfmt::skip]
+const DESCRIPTOR_OUTPUT: &[u8] = &[
+    0x05, 0x0a,       //   Usage Page (Ordinal)
+    0x19, 0x01,       //   Usage Minimum (1)
+    0x29, 0x08,       //   Usage Maximum (8)
+    0x15, 0x00,       //   Logical Minimum (0)
+    0x25, 0x01,       //   Logical Maximum (1)
+    0x75, 0x01,       //   Report Size (1)
+    0x95, 0x08,       //   Report Count (8)
+    0x91, 0x02,       //   Output (Data, Variable, Absolute)
+    0x05, 0x08,       //   Usage Page (LEDs)
+    0x09, 0x4b,       //   Usage (Generic Indicator)
+    0x09, 0x4b,       //   Usage (Generic Indicator)
+    0x26, 0xff, 0x00, //   Logical Maximum (255)
+    0x75, 0x08,       //   Report Size (8)
+    0x95, 0x02,       //   Report Count (2)
+    0x91, 0x02,       //   Output (Data, Variable, Absolute)
+    0xc0,             // End Collection
+];
+
+const DESCRIPTOR_LEN: usize = DESCRIPTOR_HEADER.len()
+    + AXES_COUNT * 5
+    + (1 + ENCODER_AXES) * 2
+    + DESCRIPTOR_AXES.len()
+    + DESCRIPTOR_BUTTONS.len()
+    + DESCRIPTOR_OUTPUT.len();
+
+/// Report descriptor with one `Usage` item per entry of [`AXES`], one for the parameter axis and
+/// one per encoder axis
+pub static JOYSTICK_DESCRIPTOR: [u8; DESCRIPTOR_LEN] = joystick_descriptor();
+
+const fn push(descriptor: &mut [u8; DESCRIPTOR_LEN], mut at: usize, items: &[u8]) -> usize {
+    let mut i = 0;
+    while i < items.len() {
+        descriptor[at] = items[i];
+        at += 1;
+        i += 1;
+    }
+    at
+}
+
+const fn joystick_descriptor() -> [u8; DESCRIPTOR_LEN] {
+    let mut descriptor = [0; DESCRIPTOR_LEN];
+    let mut at = push(&mut descriptor, 0, DESCRIPTOR_HEADER);
+    let mut axis = 0;
+    while axis < AXES_COUNT {
+        // Usage (axis), with its usage page
+        at = push(&mut descriptor, at, &AXES[axis].usage.item());
+        axis += 1;
+    }
+    // Usage (parameter axis)
+    at = push(&mut descriptor, at, &[0x09, PARAMETER_AXIS_USAGE as u8]);
+    let mut encoder = 0;
+    while encoder < ENCODER_AXES {
+        // Usage (encoder axis)
+        at = push(
+            &mut descriptor,
+            at,
+            &[0x09, ENCODER_AXIS_USAGES[encoder] as u8],
+        );
+        encoder += 1;
+    }
+    at = push(&mut descriptor, at, DESCRIPTOR_AXES);
+    at = push(&mut descriptor, at, DESCRIPTOR_BUTTONS);
+    push(&mut descriptor, at, DESCRIPTOR_OUTPUT);
+    descriptor
+}
+
+const REPORT_LEN: usize = REPORT_AXES * 2 + BUTTONS / 8;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct JoystickReport {
+    /// Axis values in [`AXES`] order
+    pub axes: [u16; AXES_COUNT],
+    /// Parameter potentiometer, when it's used as an axis
+    pub parameter_axis: u16,
+    /// Encoders reported as axes, in [`crate::encoder::ENCODERS`] order
+    pub encoder_axes: [u16; ENCODER_AXES],
+    /// Bit `n` is HID button `n + 1`
+    pub buttons: u64,
+}
+
+impl JoystickReport {
+    fn pack(&self) -> [u8; REPORT_LEN] {
+        let mut data = [0; REPORT_LEN];
+        let axes = self
+            .axes
+            .iter()
+            .chain([&self.parameter_axis])
+            .chain(&self.encoder_axes);
+        for (bytes, axis) in data.chunks_exact_mut(2).zip(axes) {
+            bytes.copy_from_slice(&axis.to_le_bytes());
+        }
+        let buttons_at = REPORT_AXES * 2;
+        data[buttons_at..].copy_from_slice(&self.buttons.to_le_bytes());
+        data
+    }
+}
+
+/// Output report sent by the host
+///
+/// | Byte | Content                                                                           |
+/// |------|-----------------------------------------------------------------------------------|
+/// | 0    | Annunciator lamps, bit `n` lights lamp `n` of [`crate::annunciator`]              |
+/// | 1    | Status LED brightness, `0` leaves the LED to the firmware [`crate::led`] patterns |
+/// | 2    | Backlight brightness, `0` leaves it to the parameter potentiometer                |
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct OutputReport {
+    pub lamps: u8,
+    pub status_led: u8,
+    pub backlight: u8,
+}
+
+impl OutputReport {
+    const LEN: usize = 3;
+
+    fn unpack(data: &[u8; Self::LEN]) -> Self {
+        Self {
+            lamps: data[0],
+            status_led: data[1],
+            backlight: data[2],
+        }
+    }
+}
+
+pub struct Joystick<'a, B: UsbBus> {
+    interface: Interface<'a, B, InBytes32, OutBytes8, ReportSingle>,
+}
+
+impl<'a, B: UsbBus> Joystick<'a, B> {
+    pub fn write_report(&mut self, report: &JoystickReport) -> Result<(), UsbHidError> {
+        self.interface
+            .write_report(&report.pack())
+            .map(|_| ())
+            .map_err(UsbHidError::from)
+    }
+
+    pub fn read_report(&mut self) -> usb_device::Result<OutputReport> {
+        let mut data = [0; OutputReport::LEN];
+        match self.interface.read_report(&mut data) {
+            Ok(len) if len == OutputReport::LEN => Ok(OutputReport::unpack(&data)),
+            Ok(_) => Err(UsbError::ParseError),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl<'a, B: UsbBus> DeviceClass<'a> for Joystick<'a, B> {
+    type I = Interface<'a, B, InBytes32, OutBytes8, ReportSingle>;
+
+    fn interface(&mut self) -> &mut Self::I {
+        &mut self.interface
+    }
+
+    fn reset(&mut self) {}
+
+    fn tick(&mut self) -> Result<(), UsbHidError> {
+        Ok(())
+    }
+}
+
+pub struct JoystickConfig<'a> {
+    interface: InterfaceConfig<'a, InBytes32, OutBytes8, ReportSingle>,
+}
+
+impl<'a> Default for JoystickConfig<'a> {
+    fn default() -> Self {
+        Self {
+            interface: InterfaceBuilder::with_static_descriptor(&JOYSTICK_DESCRIPTOR)
+                .unwrap()
+                .boot_device(InterfaceProtocol::None)
+                .description("Joystick")
+                .in_endpoint(10.millis())
+                .unwrap()
+                .with_out_endpoint(10.millis())
+                .unwrap()
+                .build()
+                .unwrap(),
+        }
+    }
+}
+
+impl<'a, B: UsbBus + 'a> UsbAllocatable<'a, B> for JoystickConfig<'a> {
+    type Allocated = Joystick<'a, B>;
+
+    fn allocate(self, usb_alloc: &'a UsbBusAllocator<B>) -> Self::Allocated {
+        Self::Allocated {
+            interface: Interface::new(usb_alloc, self.interface),
+        }
+    }
+}
+
+const _: () = assert!(REPORT_LEN <= 32);
@@ -0,0 +1,231 @@
+//! Keyboard shortcuts played from buttons and gestures
+//!
+//! Some simulators and add-on aircraft only accept keyboard shortcuts, so the device also
+//! enumerates as an NKRO keyboard. A [`KeyBinding`] presses key combinations when its trigger
+//! fires. Bindings are stored in [`crate::config::Config`], joystick buttons are still reported.
+use crate::gesture::Gesture;
+use crate::shift::LOGICAL_BUTTONS;
+use heapless::Vec;
+use usbd_human_interface_device::page::Keyboard;
+
+/// Unused words and steps are stored as erased flash
+const ERASED_WORD: u16 = 0xffff;
+/// Bindings stored in the config
+pub const MAX_KEY_BINDINGS: usize = 8;
+/// Key combinations of a single macro
+pub const MACRO_STEPS: usize = 4;
+/// Buttons with gesture detection: the reverse button and the calibrate button
+pub const GESTURE_BUTTONS: usize = 2;
+/// Keys pressed at once at most, every combination is a key and up to eight modifiers
+const MAX_KEYS: usize = MAX_KEY_BINDINGS * 9;
+
+/// Key with modifiers, e.g. Ctrl+Shift+G
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct KeyCombo {
+    /// Bit `n` holds modifier `LeftControl + n`, from `LeftControl` to `RightGUI`
+    pub modifiers: u8,
+    pub key: Keyboard,
+}
+
+impl KeyCombo {
+    fn keys(self) -> impl Iterator<Item = Keyboard> {
+        (0..8)
+            .filter(move |bit| self.modifiers & 1 << bit != 0)
+            .map(|bit| Keyboard::from(Keyboard::LeftControl as u8 + bit))
+            .chain([self.key])
+    }
+
+    pub fn to_word(self) -> u16 {
+        u16::from_le_bytes([self.key as u8, self.modifiers])
+    }
+
+    pub fn from_word(word: u16) -> Self {
+        let [key, modifiers] = word.to_le_bytes();
+        Self {
+            modifiers,
+            key: Keyboard::from(key),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Trigger {
+    /// Logical button, a single combination is held as long as the button, a macro is played
+    /// once on press
+    Button(u8),
+    /// Gesture of a gesture button, `0` is the reverse button and `1` the calibrate button
+    Gesture { button: u8, gesture: Gesture },
+}
+
+impl Trigger {
+    pub fn to_word(self) -> u16 {
+        match self {
+            Trigger::Button(button) => u16::from(button),
+            Trigger::Gesture { button, gesture } => {
+                let gesture = match gesture {
+                    Gesture::Click => 0,
+                    Gesture::DoubleClick => 1,
+                    Gesture::TripleClick => 2,
+                    Gesture::LongPress => 3,
+                    Gesture::Chord => 4,
+                };
+                1 << 8 | u16::from(button) << 4 | gesture
+            }
+        }
+    }
+
+    pub fn from_word(word: u16) -> Option<Self> {
+        let [low, kind] = word.to_le_bytes();
+        match kind {
+            0 if usize::from(low) < LOGICAL_BUTTONS => Some(Trigger::Button(low)),
+            1 if usize::from(low >> 4) < GESTURE_BUTTONS => Some(Trigger::Gesture {
+                button: low >> 4,
+                gesture: match low & 0xf {
+                    0 => Gesture::Click,
+                    1 => Gesture::DoubleClick,
+                    2 => Gesture::TripleClick,
+                    3 => Gesture::LongPress,
+                    4 => Gesture::Chord,
+                    _ => return None,
+                },
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Words of a binding in the config: trigger, step duration and steps
+pub const KEY_BINDING_WORDS: usize = 2 + MACRO_STEPS;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct KeyBinding {
+    pub trigger: Trigger,
+    /// Combinations pressed one after another, unused steps are `None`
+    pub steps: [Option<KeyCombo>; MACRO_STEPS],
+    /// Milliseconds each step of a macro is held, and released for before the next step
+    pub step_duration: u16,
+}
+
+impl KeyBinding {
+    pub fn to_words(&self) -> [u16; KEY_BINDING_WORDS] {
+        let mut words = [ERASED_WORD; KEY_BINDING_WORDS];
+        words[0] = self.trigger.to_word();
+        words[1] = self.step_duration;
+        for (word, step) in words[2..].iter_mut().zip(self.steps) {
+            *word = step.map_or(ERASED_WORD, KeyCombo::to_word);
+        }
+        words
+    }
+
+    /// Binding stored in `words`, `None` for an empty or invalid slot
+    pub fn from_words(words: &[u16]) -> Option<Self> {
+        Some(Self {
+            trigger: Trigger::from_word(words[0])?,
+            step_duration: words[1],
+            steps: core::array::from_fn(|step| {
+                Some(words[2 + step])
+                    .filter(|&word| word != ERASED_WORD)
+                    .map(KeyCombo::from_word)
+            }),
+        })
+    }
+
+    fn is_macro(&self) -> bool {
+        self.steps.iter().flatten().count() > 1 || matches!(self.trigger, Trigger::Gesture { .. })
+    }
+}
+
+/// Bindings of a new config, a double click of the calibrate button presses Pause
+pub const DEFAULT_KEY_BINDINGS: [Option<KeyBinding>; MAX_KEY_BINDINGS] = {
+    let mut bindings = [None; MAX_KEY_BINDINGS];
+    bindings[0] = Some(KeyBinding {
+        trigger: Trigger::Gesture {
+            button: 1,
+            gesture: Gesture::DoubleClick,
+        },
+        steps: [
+            Some(KeyCombo {
+                modifiers: 0,
+                key: Keyboard::Pause,
+            }),
+            None,
+            None,
+            None,
+        ],
+        step_duration: 50,
+    });
+    bindings
+};
+
+/// Plays [`KeyBinding`]s, keeping track of the macros in progress
+pub struct KeyMacros {
+    held: u32,
+    /// Start of the macro of every binding, in milliseconds
+    started: [Option<u32>; MAX_KEY_BINDINGS],
+}
+
+impl KeyMacros {
+    pub const fn new() -> Self {
+        Self {
+            held: 0,
+            started: [None; MAX_KEY_BINDINGS],
+        }
+    }
+
+    /// Takes held logical buttons and gestures of the gesture buttons, returns keys to report
+    pub fn update(
+        &mut self,
+        bindings: &[Option<KeyBinding>; MAX_KEY_BINDINGS],
+        held: u32,
+        gestures: [Option<Gesture>; GESTURE_BUTTONS],
+        now: u32,
+    ) -> Vec<Keyboard, MAX_KEYS> {
+        let pressed = held & !self.held;
+        self.held = held;
+
+        let mut keys = Vec::new();
+        for (binding, started) in bindings.iter().zip(self.started.iter_mut()) {
+            let Some(binding) = binding else {
+                *started = None;
+                continue;
+            };
+
+            let fired = match binding.trigger {
+                Trigger::Button(button) => pressed & 1 << button != 0,
+                Trigger::Gesture { button, gesture } => {
+                    gestures[usize::from(button)] == Some(gesture)
+                }
+            };
+
+            let combo = if binding.is_macro() {
+                if fired {
+                    *started = Some(now);
+                }
+                let duration = u32::from(binding.step_duration.max(1));
+                // not playing counts as past the last step
+                let elapsed = started.map_or(u32::MAX, |at| now.wrapping_sub(at));
+                let step = (elapsed / (duration * 2)) as usize;
+                if step >= MACRO_STEPS {
+                    *started = None;
+                    None
+                } else if elapsed % (duration * 2) < duration {
+                    binding.steps[step]
+                } else {
+                    None
+                }
+            } else {
+                match binding.trigger {
+                    Trigger::Button(button) if held & 1 << button != 0 => binding.steps[0],
+                    _ => None,
+                }
+            };
+
+            for key in combo.into_iter().flat_map(KeyCombo::keys) {
+                if !keys.contains(&key) {
+                    keys.push(key).ok();
+                }
+            }
+        }
+        keys
+    }
+}
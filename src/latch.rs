@@ -0,0 +1,60 @@
+//! Latching toggle switches
+//!
+//! Simulators bind most switch positions to one-shot commands, so a panel toggle switch held in
+//! its on position should not look like a button held forever. Logical buttons configured as
+//! latching report a short pulse on their own HID button when switched on and a pulse on their
+//! secondary HID button, the one from the [`crate::shift::ShiftLayer`] map, when switched off.
+//! Switches already on at startup pulse once so the simulator picks up their position.
+use crate::shift::LOGICAL_BUTTONS;
+
+/// Milliseconds a pulse is reported for, long enough for the host to poll it at least once
+const PULSE_DURATION: u32 = 100;
+
+/// Latching buttons of a new config, logical buttons 13 to 15 are the first three keys of the
+/// bottom row of the 4x4 button matrix, next to the shift key
+pub const DEFAULT_LATCHING: u32 = 0b111 << 13;
+
+pub struct LatchPulses {
+    previous: u32,
+    on: u32,
+    off: u32,
+    /// Start of the current pulse of every logical button, in milliseconds
+    started: [u32; LOGICAL_BUTTONS],
+}
+
+impl LatchPulses {
+    pub const fn new() -> Self {
+        Self {
+            previous: 0,
+            on: 0,
+            off: 0,
+            started: [0; LOGICAL_BUTTONS],
+        }
+    }
+
+    /// Takes held logical buttons and the mask of latching ones, returns logical buttons pulsing
+    /// for an on and an off transition
+    pub fn update(&mut self, latching: u32, held: u32, now: u32) -> (u32, u32) {
+        let changed = (held ^ self.previous) & latching;
+        self.previous = held;
+
+        for (button, started) in self.started.iter_mut().enumerate() {
+            let bit = 1 << button;
+            if changed & bit != 0 {
+                *started = now;
+                if held & bit != 0 {
+                    self.on |= bit;
+                    self.off &= !bit;
+                } else {
+                    self.off |= bit;
+                    self.on &= !bit;
+                }
+            } else if now.wrapping_sub(*started) >= PULSE_DURATION {
+                self.on &= !bit;
+                self.off &= !bit;
+            }
+        }
+
+        (self.on & latching, self.off & latching)
+    }
+}
@@ -0,0 +1,196 @@
+//! Status LED patterns
+//!
+//! Patterns are functions of the time since they started, rendered to a PWM duty on every pass of
+//! the main loop against the [`crate::clock`] milliseconds. Nothing here blocks or touches the
+//! hardware, so sequencing can be checked on the host by calling [`Pattern::level`].
+
+/// Brightness of a fully lit LED
+pub const FULL: u8 = u8::MAX;
+
+/// Blink codes count slowly, so they are readable by eye
+const CODE_ON: u32 = 300;
+const CODE_OFF: u32 = 300;
+const CODE_PAUSE: u32 = 1500;
+/// Error codes flash quickly, so they are not confused with blink codes
+const ERROR_ON: u32 = 100;
+const ERROR_OFF: u32 = 150;
+const ERROR_PAUSE: u32 = 1000;
+const DOUBLE_FLASH_ON: u32 = 80;
+const DOUBLE_FLASH_OFF: u32 = 120;
+const DOUBLE_FLASH_PAUSE: u32 = 920;
+/// Milliseconds of one breath, in and out
+const BREATHING_PERIOD: u32 = 2000;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Pattern {
+    Off,
+    On,
+    /// Constant brightness, `0..=FULL`
+    Level(u8),
+    /// `n` slow blinks followed by a pause
+    BlinkCode(u8),
+    /// `n` fast flashes followed by a pause
+    ErrorCode(u8),
+    /// Two short flashes followed by a pause
+    DoubleFlash,
+    /// Slowly fades in and out
+    Breathing,
+}
+
+/// Whether the LED is lit `elapsed` milliseconds into a repeating train of `count` pulses
+fn pulses(elapsed: u32, count: u8, on: u32, off: u32, pause: u32) -> bool {
+    let count = u32::from(count.max(1));
+    let elapsed = elapsed % (count * (on + off) + pause);
+    elapsed < count * (on + off) && elapsed % (on + off) < on
+}
+
+impl Pattern {
+    /// Brightness `elapsed` milliseconds after the pattern started, `0..=FULL`
+    pub fn level(self, elapsed: u32) -> u8 {
+        let lit = match self {
+            Pattern::Level(level) => return level,
+            Pattern::Off => false,
+            Pattern::On => true,
+            Pattern::BlinkCode(count) => pulses(elapsed, count, CODE_ON, CODE_OFF, CODE_PAUSE),
+            Pattern::ErrorCode(count) => pulses(elapsed, count, ERROR_ON, ERROR_OFF, ERROR_PAUSE),
+            Pattern::DoubleFlash => pulses(
+                elapsed,
+                2,
+                DOUBLE_FLASH_ON,
+                DOUBLE_FLASH_OFF,
+                DOUBLE_FLASH_PAUSE,
+            ),
+            Pattern::Breathing => {
+                let half = BREATHING_PERIOD / 2;
+                let phase = elapsed % BREATHING_PERIOD;
+                let ramp = if phase < half {
+                    phase
+                } else {
+                    BREATHING_PERIOD - phase
+                };
+                // squared so the fade looks even to the eye
+                let linear = ramp * u32::from(FULL) / half;
+                return (linear * linear / u32::from(FULL)) as u8;
+            }
+        };
+
+        if lit {
+            FULL
+        } else {
+            0
+        }
+    }
+}
+
+pub struct Led {
+    pattern: Pattern,
+    started: u32,
+}
+
+impl Led {
+    pub const fn new() -> Self {
+        Self {
+            pattern: Pattern::Off,
+            started: 0,
+        }
+    }
+
+    /// Switches to `pattern`, playing the current pattern again does not restart it
+    pub fn play(&mut self, pattern: Pattern, now: u32) {
+        if pattern != self.pattern {
+            self.pattern = pattern;
+            self.started = now;
+        }
+    }
+
+    /// PWM duty for a timer with `max_duty`
+    pub fn duty(&self, now: u32, max_duty: u16) -> u16 {
+        let level = self.pattern.level(now.wrapping_sub(self.started));
+        (u32::from(max_duty) * u32::from(level) / u32::from(FULL)) as u16
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const MAX_DUTY: u16 = 1000;
+
+    /// Whether `led` is lit at each of `times`
+    fn lit<const N: usize>(led: &Led, times: [u32; N]) -> [bool; N] {
+        times.map(|now| led.duty(now, MAX_DUTY) > 0)
+    }
+
+    #[test]
+    fn steady_patterns() {
+        let mut led = Led::new();
+        assert_eq!(led.duty(1234, MAX_DUTY), 0);
+        led.play(Pattern::On, 1000);
+        assert_eq!(led.duty(5000, MAX_DUTY), MAX_DUTY);
+        led.play(Pattern::Level(FULL / 5), 6000);
+        assert_eq!(led.duty(6000, MAX_DUTY), 200);
+    }
+
+    #[test]
+    fn blink_code_counts_then_pauses() {
+        let mut led = Led::new();
+        led.play(Pattern::BlinkCode(3), 10_000);
+        let period = 3 * (CODE_ON + CODE_OFF) + CODE_PAUSE;
+        let times = [0, 299, 300, 600, 1200, 1799, 2999, period, period + 300];
+        assert_eq!(
+            lit(&led, times.map(|elapsed| 10_000 + elapsed)),
+            [true, true, false, true, true, false, false, true, false]
+        );
+    }
+
+    #[test]
+    fn error_code_flashes_faster_than_blink_code() {
+        let mut led = Led::new();
+        led.play(Pattern::ErrorCode(2), 0);
+        assert_eq!(
+            lit(&led, [0, 99, 100, 250, 349, 350, 500, 1499, 1500]),
+            [true, true, false, true, true, false, false, false, true]
+        );
+    }
+
+    #[test]
+    fn double_flash() {
+        let mut led = Led::new();
+        led.play(Pattern::DoubleFlash, 0);
+        assert_eq!(
+            lit(&led, [0, 80, 200, 280, 400, 1319, 1320]),
+            [true, false, true, false, false, false, true]
+        );
+    }
+
+    #[test]
+    fn breathing_fades_in_and_out() {
+        let mut led = Led::new();
+        led.play(Pattern::Breathing, 0);
+        assert_eq!(led.duty(0, MAX_DUTY), 0);
+        assert!(led.duty(500, MAX_DUTY) < led.duty(900, MAX_DUTY));
+        assert_eq!(led.duty(BREATHING_PERIOD / 2, MAX_DUTY), MAX_DUTY);
+        assert!(led.duty(1100, MAX_DUTY) > led.duty(1500, MAX_DUTY));
+        assert_eq!(led.duty(BREATHING_PERIOD, MAX_DUTY), 0);
+    }
+
+    #[test]
+    fn playing_the_same_pattern_does_not_restart_it() {
+        let mut led = Led::new();
+        led.play(Pattern::BlinkCode(1), 0);
+        led.play(Pattern::BlinkCode(1), 400);
+        // still in the off half of the first blink, a restart would light the LED
+        assert_eq!(led.duty(400, MAX_DUTY), 0);
+
+        led.play(Pattern::BlinkCode(2), 400);
+        assert_eq!(led.duty(400, MAX_DUTY), MAX_DUTY);
+    }
+
+    #[test]
+    fn clock_wrap_around() {
+        let mut led = Led::new();
+        led.play(Pattern::ErrorCode(1), u32::MAX - 49);
+        assert_eq!(led.duty(u32::MAX, MAX_DUTY), MAX_DUTY);
+        assert_eq!(led.duty(60, MAX_DUTY), 0);
+    }
+}
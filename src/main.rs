@@ -1,34 +1,85 @@
-#![no_std]
-#![no_main]
-
+#![cfg_attr(not(test), no_std)]
+#![cfg_attr(not(test), no_main)]
+// host tests only build the modules, the firmware entry point is left out
+#![cfg_attr(test, allow(unused_imports, dead_code))]
+
+pub mod analog;
+pub mod annunciator;
+pub mod axes;
+pub mod backlight;
 pub mod button;
-
+pub mod clock;
+pub mod config;
+pub mod edges;
+pub mod encoder;
+pub mod fault;
+pub mod filter;
+pub mod gesture;
+pub mod hid;
+pub mod keymap;
+pub mod latch;
+pub mod led;
+pub mod matrix;
+pub mod media;
+pub mod parameters;
+pub mod shift;
+pub mod sync;
+pub mod telephony;
+
+#[cfg(not(test))]
 extern crate panic_semihosting;
+use analog::AnalogPins;
+use annunciator::Annunciators;
+use axes::{AXES, AXES_COUNT, PARAMETER_POT};
 use axis::effects::{Lerp, Smooth};
 use axis::{Axis, DynEffect};
+use backlight::{BacklightState, FRAME_INTERVAL, FRAME_LEN};
 use button::Button;
+use config::Config;
 use cortex_m::asm::delay;
 use cortex_m_rt::entry;
+use encoder::{Encoder, ENCODERS};
+use fault::{PotMonitor, FAULT_BUTTON};
+use filter::ChainEffect;
+use gesture::{Chord, Gesture, GestureButton, GestureTiming};
 //use cortex_m_semihosting::hprintln;
-use heapless::Vec;
-use stm32f1xx_hal::flash::{FlashSize, FlashWriter, SectorSize};
+use hid::{
+    Joystick, JoystickConfig, JoystickReport, OutputReport, AXIS_MAX, MATRIX_FIRST_BUTTON,
+    REVERSE_BUTTON,
+};
+use keymap::KeyMacros;
+use latch::LatchPulses;
+use led::{Led, Pattern};
+use matrix::Matrix;
+use parameters::{AutoSave, Pickup};
+use stm32f1xx_hal::flash::{FlashSize, SectorSize};
+use stm32f1xx_hal::spi::{self, NoMiso, NoSck, Spi};
 use stm32f1xx_hal::timer::{Channel, Tim3NoRemap};
 use stm32f1xx_hal::usb::Peripheral;
 use stm32f1xx_hal::{adc, pac, prelude::*};
+use sync::ThrottleSync;
+use telephony::{TelephonyConfig, TelephonyDevice};
 use usb_device::device::{UsbDeviceBuilder, UsbVidPid};
-use usbd_human_interface_device::device::joystick::JoystickReport;
+use usb_device::UsbError;
+use usbd_human_interface_device::device::consumer::{ConsumerControl, ConsumerControlConfig};
+use usbd_human_interface_device::device::keyboard::{NKROBootKeyboard, NKROBootKeyboardConfig};
 use usbd_human_interface_device::usb_class::UsbHidClassBuilder;
 use usbd_human_interface_device::UsbHidError;
 
-const FLASH_BASE: usize = 0x0800_0000;
-const LAST_PAGE_ADDRESS: usize = 0x0800_F800;
-const CONFIG_MAGIC: u16 = 0x0aaaa;
+/// Gesture durations, in milliseconds
+const GESTURE_TIMING: GestureTiming = GestureTiming {
+    long_press: 800,
+    multi_click: 300,
+};
+
+/// Button matrix has a diode in series with every key
+const MATRIX_DIODES: bool = true;
 
 #[derive(PartialEq, Clone, Copy, Debug)]
 /// Allowed stages to operate
 /// TODO: Implement all stages
 pub enum Stage {
-    /// Default mode, 3 axes, one button for enable/disable reverse
+    /// Default mode, all axes from [`axes::AXES`], one button for enable/disable reverse
     Normal,
     /// Multiplexes the thorttle axis. This stage made for workaround X-Plane input system which
     /// allows bind ONLY ONE action on axis, and for somre reason reverse thrust can be set for
@@ -38,98 +89,14 @@ pub enum Stage {
     CalibrationStageLow,
     /// Calibration stage for max range for all axes
     CalibrationStageHigh,
+    /// Selection of what the parameter potentiometer controls, LED duty shows selected role
+    PotRoleSelect,
 }
 
-#[repr(C)]
-struct Config {
-    throttle_axis_min: u16,
-    throttle_axis_max: u16,
-    prop_axis_min: u16,
-    prop_axis_max: u16,
-    mixture_axis_min: u16,
-    mixture_axis_max: u16,
-}
-
-impl Config {
-    fn new(writer: &FlashWriter) -> Self {
-        // TODO: Proper error handling
-        // FIXME: Reinterpret struct instead of extracting fields manually?
-        let base_offset = LAST_PAGE_ADDRESS - FLASH_BASE; // 0xF800
-        let values = writer.read(base_offset as u32, 14).unwrap();
-        let data: Vec<u16, 7> = values
-            .chunks_exact(2)
-            .map(|chunk| u16::from_le_bytes([chunk[0], chunk[1]]))
-            .collect();
-
-        if data[0] == CONFIG_MAGIC {
-            //hprintln!("Configuration loaded! {:?}", data);
-            Self {
-                throttle_axis_min: data[1],
-                throttle_axis_max: data[2],
-                prop_axis_min: data[3],
-                prop_axis_max: data[4],
-                mixture_axis_min: data[5],
-                mixture_axis_max: data[6],
-            }
-        } else {
-            //hprintln!("Loading default config");
-            Self::default()
-        }
-    }
-
-    fn save(&self, writer: &mut FlashWriter) {
-        // TODO: Proper error handling
-        // FIXME: Reinterpret struct instead of extracting fields manually?
-        let base_offset = LAST_PAGE_ADDRESS - FLASH_BASE; // 0xF800
-        let data: [u16; 7] = [
-            CONFIG_MAGIC,
-            self.throttle_axis_min,
-            self.throttle_axis_max,
-            self.prop_axis_min,
-            self.prop_axis_max,
-            self.mixture_axis_min,
-            self.mixture_axis_max,
-        ];
-        writer.page_erase(base_offset as u32).unwrap();
-
-        //hprintln!("writing...");
-        for (i, val) in data.iter().enumerate() {
-            let addr = base_offset as u32 + i as u32 * 2;
-            writer.write(addr, &val.to_le_bytes()).unwrap();
-        }
-
-        //hprintln!("done!");
-    }
-
-    fn new_thorttle(&self) -> Axis {
-        Axis::new(self.throttle_axis_min, self.throttle_axis_max, false)
-    }
-
-    fn new_prop(&self) -> Axis {
-        Axis::new(self.prop_axis_min, self.prop_axis_max, false)
-    }
-
-    fn new_mixture(&self) -> Axis {
-        Axis::new(self.mixture_axis_min, self.mixture_axis_max, false)
-    }
-}
-
-impl Default for Config {
-    fn default() -> Self {
-        Self {
-            throttle_axis_min: 3300,
-            throttle_axis_max: 4090,
-            prop_axis_min: 3300,
-            prop_axis_max: 4090,
-            mixture_axis_min: 3300,
-            mixture_axis_max: 4090,
-        }
-    }
-}
-
+#[cfg(not(test))]
 #[entry]
 fn main() -> ! {
-    let _cp = cortex_m::Peripherals::take().unwrap();
+    let cp = cortex_m::Peripherals::take().unwrap();
     let dp = pac::Peripherals::take().unwrap();
     let mut flash = dp.FLASH.constrain();
     let rcc = dp.RCC.constrain();
@@ -141,21 +108,24 @@ fn main() -> ! {
         .adcclk(2.MHz())
         .freeze(&mut flash.acr);
 
+    clock::start(cp.SYST, &clocks);
+
     // flash writer
     let mut writer = flash.writer(SectorSize::Sz1K, FlashSize::Sz64K);
 
     let mut gpioa = dp.GPIOA.split();
     let mut gpiob = dp.GPIOB.split();
+    let mut gpioc = dp.GPIOC.split();
     let mut afio = dp.AFIO.constrain();
 
     let mut state = Stage::Normal;
-    let chain: [DynEffect; 1] = [
-        Smooth::new(5).into()
-    ];
-
-    let mut thorttle_chain = chain.clone();
-    let mut prop_chain = chain.clone();
-    let mut mixture_chain = chain.clone();
+    // glitch filter of the axis, then smoothing
+    let mut chains: [[ChainEffect; 2]; AXES_COUNT] = AXES.each_ref().map(|axis| {
+        [
+            axis.glitch_filter.clone().into(),
+            DynEffect::from(Smooth::new(5)).into(),
+        ]
+    });
 
     let mut usb_dp = gpioa.pa12.into_push_pull_output(&mut gpioa.crh);
     let indication_led = gpioa.pa7.into_alternate_push_pull(&mut gpioa.crl);
@@ -163,28 +133,117 @@ fn main() -> ! {
     let mut adc1 = adc::Adc::adc1(dp.ADC1, clocks);
     let mut pwm =
         dp.TIM3
-            .pwm_hz::<Tim3NoRemap, _, _>(indication_led, &mut afio.mapr, 1.Hz(), &clocks);
+            .pwm_hz::<Tim3NoRemap, _, _>(indication_led, &mut afio.mapr, 1.kHz(), &clocks);
 
     //pwm.enable(Channel::C1);
     pwm.enable(Channel::C2);
     //pwm.enable(Channel::C3);
 
     pwm.set_duty(Channel::C2, 0);
-
-    let mut throttle_pot = gpioa.pa3.into_analog(&mut gpioa.crl);
-    let mut prop_pot = gpioa.pa0.into_analog(&mut gpioa.crl);
-    let mut mixture_pot = gpioa.pa2.into_analog(&mut gpioa.crl);
-    let mut calibrate_pot = gpioa.pa4.into_analog(&mut gpioa.crl);
+    let mut led = Led::new();
+
+    let mut analog_pins = AnalogPins::new(
+        gpioa.pa0,
+        gpioa.pa2,
+        gpioa.pa3,
+        gpioa.pa4,
+        gpioa.pa5,
+        gpioa.pa6,
+        &mut gpioa.crl,
+        gpiob.pb0,
+        gpiob.pb1,
+        &mut gpiob.crl,
+    );
+    // axes::AXES is checked at compile time to not assign a pin twice
+    let mut pots = AXES.map(|axis| analog_pins.take(axis.pin).unwrap());
+    let mut parameter_pot = analog_pins.take(PARAMETER_POT).unwrap();
+    let mut pot_monitors = [PotMonitor::new(); AXES_COUNT];
 
     let mut config = Config::new(&writer);
 
-    let mut throttle_axis = config.new_thorttle();
-    let mut prop_axis = config.new_prop();
-    let mut mixture_axis = config.new_mixture();
-    let mut calibrate_value = Axis::new(0, 4096, true);
-
-    let reverse_button = Button::new(gpioa.pa1.into_pull_up_input(&mut gpioa.crl));
-    let mut calibrate_button = Button::new(gpiob.pb12.into_pull_up_input(&mut gpiob.crh));
+    let mut axes = config.new_axes();
+    let mut parameter_value = Axis::new(0, 4096, true);
+    let mut parameter_pickup = Pickup::new();
+    let mut parameter_save = AutoSave::new();
+
+    let mut exti = dp.EXTI;
+    edges::start(
+        gpioa.pa1.into_pull_up_input(&mut gpioa.crl),
+        gpiob.pb12.into_pull_up_input(&mut gpiob.crh),
+        gpiob.pb13.into_pull_up_input(&mut gpiob.crh),
+        &mut afio,
+        &mut exti,
+    );
+    let mut reverse_button = GestureButton::new(Button::new(&edges::REVERSE), GESTURE_TIMING);
+    let mut calibrate_button = GestureButton::new(Button::new(&edges::CALIBRATE), GESTURE_TIMING);
+    // reverse + calibrate chord selects parameter potentiometer role
+    let mut role_select_chord = Chord::new(0b11);
+    let mut sync_switch = Button::new(&edges::SYNC);
+    let mut matrix = Matrix::new(
+        [
+            gpiob.pb5.into_open_drain_output(&mut gpiob.crl).erase(),
+            gpiob.pb6.into_open_drain_output(&mut gpiob.crl).erase(),
+            gpiob.pb7.into_open_drain_output(&mut gpiob.crl).erase(),
+            gpiob.pb8.into_open_drain_output(&mut gpiob.crh).erase(),
+        ],
+        [
+            gpiob.pb9.into_pull_up_input(&mut gpiob.crh).erase(),
+            gpiob.pb10.into_pull_up_input(&mut gpiob.crh).erase(),
+            gpiob.pb11.into_pull_up_input(&mut gpiob.crh).erase(),
+            gpiob.pb14.into_pull_up_input(&mut gpiob.crh).erase(),
+        ],
+        MATRIX_DIODES,
+    );
+    let mut throttle_sync = ThrottleSync::new();
+    // PA15, PB3 and PB4 are JTAG pins after reset, SWD keeps working without them
+    let (pa15, pb3, pb4) = afio.mapr.disable_jtag(gpioa.pa15, gpiob.pb3, gpiob.pb4);
+    // reverser unlocked, autothrottle armed, gear in transit
+    let mut annunciators = Annunciators::new([
+        pb3.into_push_pull_output(&mut gpiob.crl).erase(),
+        pb4.into_push_pull_output(&mut gpiob.crl).erase(),
+        pa15.into_push_pull_output(&mut gpioa.crh).erase(),
+    ]);
+    let mut host_output = OutputReport::default();
+    let mut latch_pulses = LatchPulses::new();
+    let mut key_macros = KeyMacros::new();
+    let mut consumer_sent = None;
+    let mut telephony_sent = None;
+    encoder::start(
+        dp.TIM2,
+        [
+            (
+                gpioa.pa8.into_pull_up_input(&mut gpioa.crh).erase(),
+                gpioa.pa9.into_pull_up_input(&mut gpioa.crh).erase(),
+            ),
+            (
+                gpioa.pa10.into_pull_up_input(&mut gpioa.crh).erase(),
+                gpioc.pc14.into_pull_up_input(&mut gpioc.crh).erase(),
+            ),
+        ],
+        &clocks,
+    );
+    let mut encoders = ENCODERS.each_ref().map(Encoder::new);
+
+    // only MOSI is needed to drive the backlight strip
+    let backlight_data = gpiob.pb15.into_alternate_push_pull(&mut gpiob.crh);
+    let backlight_spi = Spi::spi2(
+        dp.SPI2,
+        (NoSck, NoMiso, backlight_data),
+        spi::Mode {
+            polarity: spi::Polarity::IdleLow,
+            phase: spi::Phase::CaptureOnFirstTransition,
+        },
+        3.MHz(),
+        clocks,
+    );
+    let dma1 = dp.DMA1.split();
+    let mut backlight_dma = Some((
+        backlight_spi.with_tx_dma(dma1.5),
+        cortex_m::singleton!(: [u8; FRAME_LEN] = [0; FRAME_LEN]).unwrap(),
+    ));
+    let mut backlight_transfer = None;
+    let mut backlight_state = BacklightState::Panel;
+    let mut last_frame = 0;
 
     assert!(clocks.usbclk_valid());
     usb_dp.set_low();
@@ -197,8 +256,11 @@ fn main() -> ! {
     };
 
     let usb_bus = stm32f1xx_hal::usb::UsbBus::new(usb);
-    let mut joystick = UsbHidClassBuilder::new()
-        .add_device(usbd_human_interface_device::device::joystick::JoystickConfig::default())
+    let mut usb_hid = UsbHidClassBuilder::new()
+        .add_device(JoystickConfig::default())
+        .add_device(NKROBootKeyboardConfig::default())
+        .add_device(ConsumerControlConfig::default())
+        .add_device(TelephonyConfig::default())
         .build(&usb_bus);
     let mut usb_dev = UsbDeviceBuilder::new(&usb_bus, UsbVidPid(0x16c0, 0x27de))
         .manufacturer("Blue Skies")
@@ -207,39 +269,143 @@ fn main() -> ! {
         .device_class(0x03)
         .build();
 
+    let mut last_tick = clock::millis();
     loop {
-        calibrate_value.update(
-            adc1.read(&mut calibrate_pot).unwrap_or_default(),
-            core::iter::empty(),
-        );
-        let step_filter_factor = calibrate_value.output(5, 20);
-        throttle_axis.step_filter_factor = step_filter_factor;
-        prop_axis.step_filter_factor = step_filter_factor;
-        mixture_axis.step_filter_factor = step_filter_factor;
-
-        let throttle_readings = adc1.read(&mut throttle_pot).unwrap_or_default();
-        let prop_readings = adc1.read(&mut prop_pot).unwrap_or_default();
-        let mixture_readings = adc1.read(&mut mixture_pot).unwrap_or_default();
-
-        //hprintln!("{} {} {}", throttle_readings, prop_readings, mixture_readings);
+        parameter_value.update(parameter_pot.read(&mut adc1), core::iter::empty());
+        for axis in axes.iter_mut() {
+            axis.step_filter_factor = config.parameters.filter;
+        }
+
+        let mut readings = [0; AXES_COUNT];
+        for (i, pot) in pots.iter_mut().enumerate() {
+            readings[i] = pot.read(&mut adc1);
+        }
+
+        let now = clock::millis();
+        // keyboard idle timing counts milliseconds
+        for _ in 0..now.wrapping_sub(last_tick) {
+            match usb_hid.tick() {
+                Err(UsbHidError::WouldBlock) => {}
+                Ok(_) => {}
+                Err(e) => {
+                    core::panic!("Failed to process keyboard tick: {:?}", e)
+                }
+            }
+        }
+        last_tick = now;
+        let reverse_gesture = reverse_button.update(now);
+        let mut calibrate_gesture = calibrate_button.update(now);
+        let matrix_keys = matrix.scan(now);
+        let mut encoder_buttons = 0;
+        for (encoder, detents) in encoders.iter_mut().zip(encoder::take_detents()) {
+            encoder_buttons |= encoder.update(detents, now);
+        }
+        let held = u32::from(reverse_button.held()) | u32::from(calibrate_button.held()) << 1;
+        let role_select = role_select_chord.update(held) == Some(Gesture::Chord);
+        if role_select {
+            reverse_button.cancel();
+            calibrate_button.cancel();
+            calibrate_gesture = None;
+        }
+
+        //hprintln!("{:?}", readings);
         match state {
             Stage::NormalXplane => todo!(),
             Stage::Normal => {
-                let buttons = if reverse_button.pressed() {
-                    0b00000001
-                } else {
-                    0b00000000
-                };
+                // role selection and calibration leave the parameters as the knob last set them
+                let adjusted = config.parameters.adjust(
+                    config.pot_role,
+                    &mut parameter_value,
+                    &mut parameter_pickup,
+                );
+                if parameter_save.update(adjusted, now) {
+                    config.save(&mut writer);
+                }
+
+                let mut held = matrix_keys << MATRIX_FIRST_BUTTON | encoder_buttons;
+                if reverse_button.held() {
+                    held |= REVERSE_BUTTON;
+                }
+                let keys = key_macros.update(
+                    &config.key_bindings,
+                    held,
+                    [reverse_gesture, calibrate_gesture],
+                    now,
+                );
+                match usb_hid
+                    .device::<NKROBootKeyboard<_>, _>()
+                    .write_report(keys)
+                {
+                    Err(UsbHidError::WouldBlock | UsbHidError::Duplicate) => {}
+                    Ok(_) => {}
+                    Err(e) => {
+                        core::panic!("Failed to write keyboard report: {:?}", e)
+                    }
+                }
+
+                // reports are only sent on change, until the endpoint accepts them
+                let (consumer, telephony) = media::reports(&config.media_bindings, held);
+                if consumer_sent != Some(consumer) {
+                    match usb_hid
+                        .device::<ConsumerControl<_>, _>()
+                        .write_report(&consumer)
+                    {
+                        Err(UsbError::WouldBlock) => {}
+                        Ok(_) => consumer_sent = Some(consumer),
+                        Err(e) => {
+                            core::panic!("Failed to write consumer report: {:?}", e)
+                        }
+                    }
+                }
+                if telephony_sent != Some(telephony) {
+                    match usb_hid
+                        .device::<TelephonyDevice<_>, _>()
+                        .write_report(&telephony)
+                    {
+                        Err(UsbHidError::WouldBlock) => {}
+                        Ok(_) => telephony_sent = Some(telephony),
+                        Err(e) => {
+                            core::panic!("Failed to write telephony report: {:?}", e)
+                        }
+                    }
+                }
+
+                // latching switches only pulse, on their own button or their secondary one
+                let (on, off) = latch_pulses.update(config.latching, held, now);
+                let mut buttons = config.shift.apply(held & !config.latching)
+                    | u64::from(on)
+                    | config.shift.secondary(off);
+
+                // faulty axes are frozen at their last good value, the error code tells which
+                match pot_monitors.iter().position(PotMonitor::faulty) {
+                    Some(axis) => {
+                        buttons |= FAULT_BUTTON;
+                        led.play(Pattern::ErrorCode(axis as u8 + 1), now);
+                        backlight_state = BacklightState::Fault;
+                    }
+                    None if host_output.status_led != 0 => {
+                        led.play(Pattern::Level(host_output.status_led), now);
+                        backlight_state = BacklightState::Panel;
+                    }
+                    None => {
+                        led.play(Pattern::Off, now);
+                        backlight_state = BacklightState::Panel;
+                    }
+                }
+
+                let mut outputs =
+                    core::array::from_fn(|i| config.parameters.shape(axes[i].output(0, AXIS_MAX)));
+                throttle_sync.apply(sync_switch.toggle(now), &mut outputs);
 
                 let report = JoystickReport {
-                    x: throttle_axis.output(0, 1024),
-                    y: prop_axis.output(0, 1024),
-                    z: mixture_axis.output(0, 1024),
+                    axes: outputs,
+                    parameter_axis: config.parameters.axis,
+                    encoder_axes: encoder::axis_values(&encoders),
                     buttons,
                 };
 
                 delay(1);
-                match joystick.device().write_report(&report) {
+                match usb_hid.device::<Joystick<_>, _>().write_report(&report) {
                     Err(UsbHidError::WouldBlock) => {}
                     Ok(_) => {}
                     Err(e) => {
@@ -247,39 +413,80 @@ fn main() -> ! {
                     }
                 }
 
-                if !usb_dev.poll(&mut [&mut joystick]) {
-                    throttle_axis.update(throttle_readings, thorttle_chain.iter_mut());
-                    prop_axis.update(prop_readings, prop_chain.iter_mut());
-                    mixture_axis.update(mixture_readings, mixture_chain.iter_mut());
+                // malformed output reports are dropped, the last good one stays in effect
+                if let Ok(output) = usb_hid.device::<Joystick<_>, _>().read_report() {
+                    annunciators.set(output.lamps);
+                    host_output = output;
+                }
 
-                    if calibrate_button.click() {
-                        state = Stage::CalibrationStageLow;
+                if !usb_dev.poll(&mut [&mut usb_hid]) {
+                    for (i, axis) in axes.iter_mut().enumerate() {
+                        if pot_monitors[i].check(readings[i], &config.calibration[i], now) {
+                            filter::update(axis, readings[i], &mut chains[i]);
+                        }
                     }
                 }
+
+                if role_select {
+                    state = Stage::PotRoleSelect;
+                } else if calibrate_gesture == Some(Gesture::Click) {
+                    state = Stage::CalibrationStageLow;
+                }
             }
             Stage::CalibrationStageLow => {
-                pwm.set_duty(Channel::C2, pwm.get_max_duty() / 8);
-                config.throttle_axis_min = throttle_readings.min(config.throttle_axis_max);
-                config.prop_axis_min = prop_readings.min(config.prop_axis_max);
-                config.mixture_axis_min = mixture_readings.min(config.mixture_axis_max);
-                if calibrate_button.click() {
+                led.play(Pattern::DoubleFlash, now);
+                backlight_state = BacklightState::CalibrationLow;
+                for (calibration, reading) in config.calibration.iter_mut().zip(readings) {
+                    calibration.min = reading.min(calibration.max);
+                }
+                if calibrate_gesture == Some(Gesture::Click) {
                     state = Stage::CalibrationStageHigh;
                 };
             }
             Stage::CalibrationStageHigh => {
-                pwm.set_duty(Channel::C2, pwm.get_max_duty() / 2);
-                config.throttle_axis_max = throttle_readings.max(config.throttle_axis_min);
-                config.prop_axis_max = prop_readings.max(config.prop_axis_min);
-                config.mixture_axis_max = mixture_readings.max(config.mixture_axis_min);
-                if calibrate_button.click() {
+                led.play(Pattern::Breathing, now);
+                backlight_state = BacklightState::CalibrationHigh;
+                for (calibration, reading) in config.calibration.iter_mut().zip(readings) {
+                    calibration.max = reading.max(calibration.min);
+                }
+                if calibrate_gesture == Some(Gesture::Click) {
                     config.save(&mut writer);
-                    pwm.set_duty(Channel::C2, 0);
-                    throttle_axis = config.new_thorttle();
-                    prop_axis = config.new_prop();
-                    mixture_axis = config.new_mixture();
+                    axes = config.new_axes();
                     state = Stage::Normal;
                 };
             }
+            Stage::PotRoleSelect => {
+                led.play(Pattern::BlinkCode(config.pot_role as u8 + 1), now);
+                backlight_state = BacklightState::RoleSelect(config.pot_role as u8 + 1);
+                match calibrate_gesture {
+                    Some(Gesture::Click) => config.pot_role = config.pot_role.next(),
+                    Some(Gesture::LongPress) => {
+                        config.save(&mut writer);
+                        state = Stage::Normal;
+                    }
+                    _ => {}
+                }
+            }
         };
+
+        pwm.set_duty(Channel::C2, led.duty(now, pwm.get_max_duty()));
+
+        if now.wrapping_sub(last_frame) >= FRAME_INTERVAL {
+            if let Some((spi, buffer)) = backlight_dma.take() {
+                let brightness = match host_output.backlight {
+                    0 => config.parameters.backlight as u8,
+                    brightness => brightness,
+                };
+                let leds = backlight::frame(backlight_state, config.backlight_color, brightness);
+                backlight::encode(&leds, buffer);
+                backlight_transfer = Some(spi.write(buffer));
+                last_frame = now;
+            }
+        }
+
+        if let Some(transfer) = backlight_transfer.take_if(|transfer| transfer.is_done()) {
+            let (buffer, spi) = transfer.wait();
+            backlight_dma = Some((spi, buffer));
+        }
     }
 }
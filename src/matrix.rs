@@ -0,0 +1,77 @@
+//! Row/column button matrix
+//!
+//! Rows are open drain outputs driven low one at a time, columns are inputs with pull-ups. With
+//! a diode in series with every key any combination of keys can be held. Without diodes three
+//! keys held in the corners of a rectangle make the fourth one look pressed, so rows sharing
+//! more than one held column are ambiguous and keep their previous state.
+use crate::button::Debounce;
+use embedded_hal::digital::v2::{InputPin, OutputPin};
+
+/// Cycles to wait for a column to settle after a row is driven
+const SETTLE_CYCLES: u32 = 48;
+
+pub struct Matrix<R, C, const ROWS: usize, const COLS: usize> {
+    rows: [R; ROWS],
+    cols: [C; COLS],
+    keys: [[Debounce; COLS]; ROWS],
+    diodes: bool,
+}
+
+impl<R, C, const ROWS: usize, const COLS: usize> Matrix<R, C, ROWS, COLS>
+where
+    R: OutputPin,
+    C: InputPin,
+{
+    pub fn new(mut rows: [R; ROWS], cols: [C; COLS], diodes: bool) -> Self {
+        assert!(ROWS * COLS <= 32);
+        for row in rows.iter_mut() {
+            row.set_high().ok();
+        }
+
+        Self {
+            rows,
+            cols,
+            keys: [[Debounce::new(); COLS]; ROWS],
+            diodes,
+        }
+    }
+
+    fn read_row(&mut self, row: usize) -> u32 {
+        self.rows[row].set_low().ok();
+        cortex_m::asm::delay(SETTLE_CYCLES);
+        let held = self
+            .cols
+            .iter()
+            .enumerate()
+            .filter(|(_, col)| col.is_low().unwrap_or_default())
+            .fold(0, |held, (col, _)| held | 1 << col);
+        self.rows[row].set_high().ok();
+        held
+    }
+
+    /// Returns debounced keys as a bitmask, key `row * COLS + col` is bit `row * COLS + col`
+    pub fn scan(&mut self, now: u32) -> u32 {
+        let mut raw = [0u32; ROWS];
+        for (row, held) in raw.iter_mut().enumerate() {
+            *held = self.read_row(row);
+        }
+
+        let mut keys = 0;
+        for (row, held) in raw.iter().enumerate() {
+            let ambiguous = !self.diodes
+                && raw.iter().enumerate().any(|(other, other_held)| {
+                    other != row && (held & other_held).count_ones() > 1
+                });
+
+            for (col, key) in self.keys[row].iter_mut().enumerate() {
+                if !ambiguous {
+                    key.update(held & 1 << col != 0, now);
+                }
+                if key.state() {
+                    keys |= 1 << (row * COLS + col);
+                }
+            }
+        }
+        keys
+    }
+}
@@ -0,0 +1,101 @@
+//! Consumer control and telephony actions on buttons
+//!
+//! A [`MediaBinding`] reports a `Consumer` usage, e.g. volume or mute, or a `Telephony` usage,
+//! e.g. hook switch, while its logical button is held. Bindings are stored in
+//! [`crate::config::Config`], joystick buttons are still reported.
+use crate::shift::LOGICAL_BUTTONS;
+use crate::telephony::{TelephonyReport, TELEPHONY_USAGES};
+use usbd_human_interface_device::device::consumer::MultipleConsumerReport;
+use usbd_human_interface_device::page::{Consumer, Telephony};
+
+/// Bindings stored in the config
+pub const MAX_MEDIA_BINDINGS: usize = 8;
+/// Words of a binding in the config: button and usage
+pub const MEDIA_BINDING_WORDS: usize = 2;
+/// Consumer usages reported at once by [`MultipleConsumerReport`]
+const CONSUMER_USAGES: usize = 4;
+/// Set in the stored usage word of telephony usages
+const TELEPHONY_FLAG: u16 = 1 << 15;
+/// Bindings of a new config, the last two keys of the third matrix row turn the volume down and
+/// up
+pub const DEFAULT_MEDIA_BINDINGS: [Option<MediaBinding>; MAX_MEDIA_BINDINGS] = {
+    let mut bindings = [None; MAX_MEDIA_BINDINGS];
+    bindings[0] = Some(MediaBinding {
+        button: 11,
+        usage: MediaUsage::Consumer(Consumer::VolumeDecrement),
+    });
+    bindings[1] = Some(MediaBinding {
+        button: 12,
+        usage: MediaUsage::Consumer(Consumer::VolumeIncrement),
+    });
+    bindings
+};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MediaUsage {
+    Consumer(Consumer),
+    Telephony(Telephony),
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MediaBinding {
+    /// Logical button
+    pub button: u8,
+    pub usage: MediaUsage,
+}
+
+impl MediaBinding {
+    pub fn to_words(&self) -> [u16; MEDIA_BINDING_WORDS] {
+        let usage = match self.usage {
+            MediaUsage::Consumer(usage) => u16::from(usage),
+            MediaUsage::Telephony(usage) => TELEPHONY_FLAG | u16::from(u8::from(usage)),
+        };
+        [u16::from(self.button), usage]
+    }
+
+    /// Binding stored in `words`, `None` for an empty or invalid slot
+    pub fn from_words(words: &[u16]) -> Option<Self> {
+        let button = u8::try_from(words[0])
+            .ok()
+            .filter(|&button| usize::from(button) < LOGICAL_BUTTONS)?;
+        let usage = if words[1] & TELEPHONY_FLAG != 0 {
+            MediaUsage::Telephony(Telephony::from(
+                u8::try_from(words[1] & !TELEPHONY_FLAG).ok()?,
+            ))
+        } else {
+            MediaUsage::Consumer(Consumer::from(words[1]))
+        };
+        Some(Self { button, usage })
+    }
+}
+
+/// Reports of the bindings whose buttons are held, later bindings are dropped once a report is
+/// full
+pub fn reports(
+    bindings: &[Option<MediaBinding>; MAX_MEDIA_BINDINGS],
+    held: u32,
+) -> (MultipleConsumerReport, TelephonyReport) {
+    let mut consumer = [Consumer::Unassigned; CONSUMER_USAGES];
+    let mut telephony = TelephonyReport::default();
+    let (mut consumers, mut telephonies) = (0, 0);
+
+    let active = bindings
+        .iter()
+        .flatten()
+        .filter(|binding| held & 1 << binding.button != 0);
+    for binding in active {
+        match binding.usage {
+            MediaUsage::Consumer(usage) if consumers < CONSUMER_USAGES => {
+                consumer[consumers] = usage;
+                consumers += 1;
+            }
+            MediaUsage::Telephony(usage) if telephonies < TELEPHONY_USAGES => {
+                telephony.usages[telephonies] = usage;
+                telephonies += 1;
+            }
+            _ => {}
+        }
+    }
+
+    (MultipleConsumerReport { codes: consumer }, telephony)
+}
@@ -0,0 +1,292 @@
+//! Settings adjusted with the parameter potentiometer
+//!
+//! The potentiometer controls one [`PotRole`] at a time, every other parameter keeps its stored
+//! value until its role is selected again.
+use crate::hid::AXIS_MAX;
+use axis::Axis;
+
+/// Deadzone at both ends of axes travel is at most `MAX_DEADZONE` report units
+const MAX_DEADZONE: u16 = 64;
+/// Curve is set in percents, 0 is linear response and 100 is fully quadratic
+const MAX_CURVE: u16 = 100;
+/// Milliseconds the potentiometer has to rest before an adjusted parameter is saved
+const SETTLE_MS: u32 = 2000;
+/// Milliseconds between automatic saves, a flash page only endures about 10 000 erases
+const MIN_SAVE_INTERVAL: u32 = 5 * 60 * 1000;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+#[repr(u16)]
+pub enum PotRole {
+    /// Step filter factor of all axes
+    #[default]
+    Filter = 0,
+    /// Deadzone at both ends of all axes
+    Deadzone = 1,
+    /// Response curve of all axes
+    Curve = 2,
+    /// Extra HID axis, e.g. elevator trim
+    Axis = 3,
+    /// Brightness of the panel backlight
+    Backlight = 4,
+}
+
+impl PotRole {
+    pub const COUNT: u16 = 5;
+
+    pub fn next(self) -> Self {
+        Self::from_word((self as u16 + 1) % Self::COUNT).unwrap_or(Self::Filter)
+    }
+
+    pub fn from_word(word: u16) -> Option<Self> {
+        match word {
+            0 => Some(Self::Filter),
+            1 => Some(Self::Deadzone),
+            2 => Some(Self::Curve),
+            3 => Some(Self::Axis),
+            4 => Some(Self::Backlight),
+            _ => None,
+        }
+    }
+
+    /// Output range of the potentiometer for this role
+    fn range(self) -> (u16, u16) {
+        match self {
+            Self::Filter => (5, 20),
+            Self::Deadzone => (0, MAX_DEADZONE),
+            Self::Curve => (0, MAX_CURVE),
+            Self::Axis => (0, AXIS_MAX),
+            Self::Backlight => (0, u8::MAX as u16),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Parameters {
+    pub filter: u16,
+    pub deadzone: u16,
+    pub curve: u16,
+    pub axis: u16,
+    pub backlight: u16,
+}
+
+/// Soft takeover of the parameter potentiometer
+///
+/// After a role change, and at startup, the potentiometer only takes over the parameter once it
+/// reaches or passes the stored value, so switching roles doesn't jump the parameter to wherever
+/// the knob happens to be.
+#[derive(Clone, Copy, Default)]
+pub struct Pickup {
+    role: Option<PotRole>,
+    /// Last potentiometer output while it hasn't picked up the parameter yet
+    waiting: Option<u16>,
+}
+
+impl Pickup {
+    pub const fn new() -> Self {
+        Self {
+            role: None,
+            waiting: None,
+        }
+    }
+
+    /// Whether the potentiometer at `value` controls the parameter of `role` stored as `stored`
+    fn update(&mut self, role: PotRole, value: u16, stored: u16) -> bool {
+        if self.role != Some(role) {
+            self.role = Some(role);
+            self.waiting = Some(value);
+        }
+        if let Some(last) = self.waiting {
+            let passed = (last.min(value)..=last.max(value)).contains(&stored);
+            self.waiting = if passed { None } else { Some(value) };
+        }
+        self.waiting.is_none()
+    }
+}
+
+/// Saving of parameters adjusted with the potentiometer
+///
+/// A parameter is saved once the potentiometer rests for [`SETTLE_MS`], so turning the knob
+/// doesn't write every intermediate value. Saves are rate limited, a later change is saved once
+/// [`MIN_SAVE_INTERVAL`] has passed.
+#[derive(Clone, Copy, Default)]
+pub struct AutoSave {
+    /// Time of the last change which isn't saved yet
+    changed: Option<u32>,
+    last_save: Option<u32>,
+}
+
+impl AutoSave {
+    pub const fn new() -> Self {
+        Self {
+            changed: None,
+            last_save: None,
+        }
+    }
+
+    /// Takes whether the parameter `changed`, returns `true` when the config should be saved
+    pub fn update(&mut self, changed: bool, now: u32) -> bool {
+        if changed {
+            self.changed = Some(now);
+        }
+        let Some(changed) = self.changed else {
+            return false;
+        };
+        let settled = now.wrapping_sub(changed) >= SETTLE_MS;
+        let allowed = !matches!(
+            self.last_save,
+            Some(last) if now.wrapping_sub(last) < MIN_SAVE_INTERVAL
+        );
+        if settled && allowed {
+            self.changed = None;
+            self.last_save = Some(now);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl Parameters {
+    /// Sets the parameter of `role` from the potentiometer, once it has picked the parameter up.
+    /// Returns whether the parameter changed
+    pub fn adjust(&mut self, role: PotRole, pot: &mut Axis, pickup: &mut Pickup) -> bool {
+        let (min, max) = role.range();
+        let value = pot.output(min, max);
+        let parameter = self.get_mut(role);
+        let changed = pickup.update(role, value, *parameter) && *parameter != value;
+        if changed {
+            *parameter = value;
+        }
+        changed
+    }
+
+    pub fn get_mut(&mut self, role: PotRole) -> &mut u16 {
+        match role {
+            PotRole::Filter => &mut self.filter,
+            PotRole::Deadzone => &mut self.deadzone,
+            PotRole::Curve => &mut self.curve,
+            PotRole::Axis => &mut self.axis,
+            PotRole::Backlight => &mut self.backlight,
+        }
+    }
+
+    /// Applies deadzone and response curve to an axis output in `0..=AXIS_MAX` range
+    pub fn shape(&self, value: u16) -> u16 {
+        let deadzone = u32::from(self.deadzone.min(MAX_DEADZONE));
+        let curve = u32::from(self.curve.min(MAX_CURVE));
+        let max = u32::from(AXIS_MAX);
+
+        let value = u32::from(value).clamp(deadzone, max - deadzone) - deadzone;
+        let linear = value * max / (max - deadzone * 2);
+        let quadratic = linear * linear / max;
+        ((linear * (MAX_CURVE as u32 - curve) + quadratic * curve) / MAX_CURVE as u32) as u16
+    }
+}
+
+impl Default for Parameters {
+    fn default() -> Self {
+        Self {
+            filter: 10,
+            deadzone: 0,
+            curve: 0,
+            axis: AXIS_MAX / 2,
+            backlight: 128,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn pickup_waits_for_stored_value() {
+        let mut pickup = Pickup::new();
+        assert!(!pickup.update(PotRole::Curve, 10, 50));
+        assert!(!pickup.update(PotRole::Curve, 40, 50));
+        // passing the stored value between two readings picks it up
+        assert!(pickup.update(PotRole::Curve, 60, 50));
+        assert!(pickup.update(PotRole::Curve, 20, 60));
+    }
+
+    #[test]
+    fn pickup_from_above() {
+        let mut pickup = Pickup::new();
+        assert!(!pickup.update(PotRole::Deadzone, 60, 30));
+        assert!(pickup.update(PotRole::Deadzone, 30, 30));
+    }
+
+    #[test]
+    fn role_change_waits_for_pickup_again() {
+        let mut pickup = Pickup::new();
+        assert!(pickup.update(PotRole::Filter, 10, 10));
+        assert!(!pickup.update(PotRole::Backlight, 10, 128));
+        assert!(!pickup.update(PotRole::Backlight, 100, 128));
+        assert!(pickup.update(PotRole::Backlight, 200, 128));
+    }
+
+    #[test]
+    fn linear_shape_passes_values() {
+        let parameters = Parameters::default();
+        for value in [0, 1, AXIS_MAX / 3, AXIS_MAX / 2, AXIS_MAX - 1, AXIS_MAX] {
+            assert_eq!(parameters.shape(value), value);
+        }
+    }
+
+    #[test]
+    fn deadzone_clamps_both_ends() {
+        let parameters = Parameters {
+            deadzone: 32,
+            ..Parameters::default()
+        };
+        assert_eq!(parameters.shape(0), 0);
+        assert_eq!(parameters.shape(32), 0);
+        assert_eq!(parameters.shape(AXIS_MAX / 2), AXIS_MAX / 2);
+        assert_eq!(parameters.shape(AXIS_MAX - 32), AXIS_MAX);
+        assert_eq!(parameters.shape(AXIS_MAX), AXIS_MAX);
+        // out of range deadzones are capped
+        let parameters = Parameters {
+            deadzone: u16::MAX,
+            ..Parameters::default()
+        };
+        assert_eq!(parameters.shape(AXIS_MAX), AXIS_MAX);
+    }
+
+    #[test]
+    fn curve_blends_linear_and_quadratic() {
+        let quadratic = Parameters {
+            curve: MAX_CURVE,
+            ..Parameters::default()
+        };
+        assert_eq!(quadratic.shape(AXIS_MAX / 2), AXIS_MAX / 4);
+        assert_eq!(quadratic.shape(AXIS_MAX), AXIS_MAX);
+        let half = Parameters {
+            curve: MAX_CURVE / 2,
+            ..Parameters::default()
+        };
+        assert_eq!(half.shape(AXIS_MAX / 2), (AXIS_MAX / 2 + AXIS_MAX / 4) / 2);
+        assert_eq!(half.shape(0), 0);
+    }
+
+    #[test]
+    fn auto_save_waits_for_the_pot_to_settle() {
+        let mut save = AutoSave::new();
+        assert!(!save.update(false, 0));
+        assert!(!save.update(true, 1000));
+        assert!(!save.update(true, 2000));
+        assert!(!save.update(false, 2000 + SETTLE_MS - 1));
+        assert!(save.update(false, 2000 + SETTLE_MS));
+        assert!(!save.update(false, 2000 + SETTLE_MS + 1));
+    }
+
+    #[test]
+    fn auto_save_is_rate_limited() {
+        let mut save = AutoSave::new();
+        save.update(true, 0);
+        assert!(save.update(false, SETTLE_MS));
+        save.update(true, SETTLE_MS + 10);
+        assert!(!save.update(false, SETTLE_MS * 3));
+        // the later change is saved once the interval has passed
+        assert!(save.update(false, SETTLE_MS + MIN_SAVE_INTERVAL));
+    }
+}
@@ -0,0 +1,56 @@
+//! Shift layer for buttons
+//!
+//! While the shift button is held every other button reports a different HID button, which
+//! doubles bindable actions without extra hardware. The shift button itself is not reported.
+use crate::hid::BUTTONS;
+
+/// Buttons which can be shifted: reverse button, matrix keys and anything else reported in the
+/// low 32 HID buttons. Bit `n` is logical button `n`
+pub const LOGICAL_BUTTONS: usize = 32;
+/// Shift button of a new config, logical button 16 is the last key of the 4x4 button matrix
+pub const DEFAULT_SHIFT_BUTTON: Option<u8> = Some(16);
+
+#[derive(Clone, Copy)]
+pub struct ShiftLayer {
+    /// Logical button acting as shift, `None` disables the layer
+    pub button: Option<u8>,
+    /// HID button bit reported for each logical button while shift is held, latching switches
+    /// report their off pulse on it instead
+    pub map: [u8; LOGICAL_BUTTONS],
+}
+
+impl ShiftLayer {
+    /// Maps held logical buttons to HID buttons
+    pub fn apply(&self, held: u32) -> u64 {
+        let Some(shift) = self.button.filter(|&shift| held & 1 << shift != 0) else {
+            return u64::from(held);
+        };
+
+        self.secondary(held & !(1 << shift))
+    }
+
+    /// Maps logical buttons to their HID buttons from [`ShiftLayer::map`], regardless of shift
+    pub fn secondary(&self, buttons: u32) -> u64 {
+        self.map
+            .iter()
+            .enumerate()
+            .filter(|(button, _)| buttons & 1 << button != 0)
+            .fold(0, |buttons, (_, &hid_button)| buttons | 1 << hid_button)
+    }
+
+    /// Default mapping of a logical button, right above the unshifted ones
+    pub fn default_mapping(button: usize) -> u8 {
+        (LOGICAL_BUTTONS + button) as u8
+    }
+}
+
+impl Default for ShiftLayer {
+    fn default() -> Self {
+        Self {
+            button: DEFAULT_SHIFT_BUTTON,
+            map: core::array::from_fn(Self::default_mapping),
+        }
+    }
+}
+
+const _: () = assert!(LOGICAL_BUTTONS * 2 <= BUTTONS);
@@ -0,0 +1,138 @@
+//! Throttle synchronisation for multi-engine quadrants
+//!
+//! While sync is active every engine throttle reports the position of the [`SYNC_MASTER`] lever.
+//! Once sync is released, each engine throttle keeps the last synced value until its own lever
+//! is brought to that position, so the output does not jump.
+use crate::axes::{AXES, AXES_COUNT, SYNC_MASTER};
+
+/// Lever picks up the held value when it's closer than this, in report units
+const PICKUP_WINDOW: u16 = 8;
+
+#[derive(Clone, Copy)]
+enum Lever {
+    /// Lever drives its own axis
+    Live,
+    /// Axis is held at `value` until the lever reaches it. `below` tells on which side of
+    /// `value` the lever was when sync was released
+    Held { value: u16, below: bool },
+}
+
+pub struct ThrottleSync {
+    levers: [Lever; AXES_COUNT],
+    /// Index of the lever driving the engine throttles
+    master: usize,
+    /// Axes following the master while sync is active
+    engines: [bool; AXES_COUNT],
+}
+
+impl ThrottleSync {
+    /// Sync of the engine throttles of [`AXES`]
+    pub fn new() -> Self {
+        Self::with_engines(
+            SYNC_MASTER,
+            core::array::from_fn(|i| AXES[i].engine_throttle),
+        )
+    }
+
+    fn with_engines(master: usize, engines: [bool; AXES_COUNT]) -> Self {
+        Self {
+            levers: [Lever::Live; AXES_COUNT],
+            master,
+            engines,
+        }
+    }
+
+    /// Rewrites engine throttle `outputs` according to the sync state
+    pub fn apply(&mut self, active: bool, outputs: &mut [u16; AXES_COUNT]) {
+        let master = outputs[self.master];
+        for (i, (lever, output)) in self.levers.iter_mut().zip(outputs.iter_mut()).enumerate() {
+            if i == self.master || !self.engines[i] {
+                continue;
+            }
+
+            if active {
+                *lever = Lever::Held {
+                    value: master,
+                    below: *output < master,
+                };
+                *output = master;
+            } else if let Lever::Held { value, below } = *lever {
+                let crossed = (*output < value) != below;
+                if crossed || output.abs_diff(value) <= PICKUP_WINDOW {
+                    *lever = Lever::Live;
+                } else {
+                    *output = value;
+                }
+            }
+        }
+    }
+}
+
+impl Default for ThrottleSync {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Lever 0 drives lever 1, any further axes are not engine throttles
+    fn twin() -> ThrottleSync {
+        ThrottleSync::with_engines(0, core::array::from_fn(|i| i < 2))
+    }
+
+    fn outputs(master: u16, engine: u16) -> [u16; AXES_COUNT] {
+        let mut outputs = [500; AXES_COUNT];
+        outputs[0] = master;
+        outputs[1] = engine;
+        outputs
+    }
+
+    /// Output of the engine lever after applying `sync`
+    fn engine(sync: &mut ThrottleSync, active: bool, master: u16, engine: u16) -> u16 {
+        let mut outputs = outputs(master, engine);
+        sync.apply(active, &mut outputs);
+        outputs[1]
+    }
+
+    #[test]
+    fn engaged_sync_follows_master() {
+        let mut sync = twin();
+        let mut outputs = outputs(800, 200);
+        sync.apply(true, &mut outputs);
+        assert_eq!(outputs, self::outputs(800, 800));
+        assert_eq!(engine(&mut sync, true, 900, 200), 900);
+    }
+
+    #[test]
+    fn released_sync_waits_for_pickup() {
+        let mut sync = twin();
+        engine(&mut sync, true, 800, 200);
+        // the lever is moved towards the held value, the output does not jump back
+        assert_eq!(engine(&mut sync, false, 300, 200), 800);
+        assert_eq!(engine(&mut sync, false, 300, 700), 800);
+        assert_eq!(engine(&mut sync, false, 300, 795), 795);
+        assert_eq!(engine(&mut sync, false, 300, 600), 600);
+    }
+
+    #[test]
+    fn lever_moved_past_held_value_is_picked_up() {
+        let mut sync = twin();
+        engine(&mut sync, true, 800, 900);
+        assert_eq!(engine(&mut sync, false, 800, 950), 800);
+        assert_eq!(engine(&mut sync, false, 800, 600), 600);
+        assert_eq!(engine(&mut sync, false, 800, 1000), 1000);
+    }
+
+    #[test]
+    fn live_levers_are_untouched() {
+        let mut sync = twin();
+        assert_eq!(engine(&mut sync, false, 800, 200), 200);
+        let mut outputs = outputs(800, 200);
+        sync.apply(true, &mut outputs);
+        assert_eq!(outputs[0], 800);
+        assert!(outputs[2..].iter().all(|&output| output == 500));
+    }
+}
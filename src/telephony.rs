@@ -0,0 +1,99 @@
+//! Telephony device, e.g. hook switch or phone mute for online ATC clients
+use stm32f1xx_hal::prelude::*;
+use usb_device::class_prelude::{UsbBus, UsbBusAllocator};
+use usbd_human_interface_device::page::Telephony;
+use usbd_human_interface_device::usb_class::prelude::*;
+
+/// Usages reported at once at most
+pub const TELEPHONY_USAGES: usize = 2;
+
+#[rustfmt::skip]
+static TELEPHONY_DESCRIPTOR: &[u8] = &[
+    0x05, 0x0b,                     // Usage Page (Telephony)
+    0x09, 0x05,                     // Usage (Headset)
+    0xa1, 0x01,                     // Collection (Application)
+    0x15, 0x00,                     //   Logical Minimum (0)
+    0x26, 0xff, 0x00,               //   Logical Maximum (255)
+    0x19, 0x00,                     //   Usage Minimum (0)
+    0x2a, 0xff, 0x00,               //   Usage Maximum (255)
+    0x75, 0x08,                     //   Report Size (8)
+    0x95, TELEPHONY_USAGES as u8,   //   Report Count (TELEPHONY_USAGES)
+    0x81, 0x00,                     //   Input (Data, Array, Absolute)
+    0xc0,                           // End Collection
+];
+
+/// Telephony usages currently active, unused slots are [`Telephony::Unassigned`]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TelephonyReport {
+    pub usages: [Telephony; TELEPHONY_USAGES],
+}
+
+impl Default for TelephonyReport {
+    fn default() -> Self {
+        Self {
+            usages: [Telephony::Unassigned; TELEPHONY_USAGES],
+        }
+    }
+}
+
+impl TelephonyReport {
+    fn pack(&self) -> [u8; TELEPHONY_USAGES] {
+        self.usages.map(u8::from)
+    }
+}
+
+pub struct TelephonyDevice<'a, B: UsbBus> {
+    interface: Interface<'a, B, InBytes8, OutNone, ReportSingle>,
+}
+
+impl<'a, B: UsbBus> TelephonyDevice<'a, B> {
+    pub fn write_report(&mut self, report: &TelephonyReport) -> Result<(), UsbHidError> {
+        self.interface
+            .write_report(&report.pack())
+            .map(|_| ())
+            .map_err(UsbHidError::from)
+    }
+}
+
+impl<'a, B: UsbBus> DeviceClass<'a> for TelephonyDevice<'a, B> {
+    type I = Interface<'a, B, InBytes8, OutNone, ReportSingle>;
+
+    fn interface(&mut self) -> &mut Self::I {
+        &mut self.interface
+    }
+
+    fn reset(&mut self) {}
+
+    fn tick(&mut self) -> Result<(), UsbHidError> {
+        Ok(())
+    }
+}
+
+pub struct TelephonyConfig<'a> {
+    interface: InterfaceConfig<'a, InBytes8, OutNone, ReportSingle>,
+}
+
+impl<'a> Default for TelephonyConfig<'a> {
+    fn default() -> Self {
+        Self {
+            interface: InterfaceBuilder::with_static_descriptor(TELEPHONY_DESCRIPTOR)
+                .unwrap()
+                .description("Telephony")
+                .in_endpoint(50.millis())
+                .unwrap()
+                .without_out_endpoint()
+                .build()
+                .unwrap(),
+        }
+    }
+}
+
+impl<'a, B: UsbBus + 'a> UsbAllocatable<'a, B> for TelephonyConfig<'a> {
+    type Allocated = TelephonyDevice<'a, B>;
+
+    fn allocate(self, usb_alloc: &'a UsbBusAllocator<B>) -> Self::Allocated {
+        Self::Allocated {
+            interface: Interface::new(usb_alloc, self.interface),
+        }
+    }
+}
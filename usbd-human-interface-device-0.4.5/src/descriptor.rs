@@ -46,6 +46,16 @@ pub enum HidProtocol {
     Report = 0x01,
 }
 
+/// Report type of `GET_REPORT` and `SET_REPORT` requests, the high byte of the request value
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromPrimitive, IntoPrimitive)]
+#[repr(u8)]
+pub enum ReportType {
+    Input = 0x01,
+    Output = 0x02,
+    Feature = 0x03,
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, TryFromPrimitive, IntoPrimitive)]
 #[repr(u8)]
 pub(crate) enum HidRequest {
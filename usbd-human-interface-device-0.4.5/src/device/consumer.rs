@@ -129,15 +129,13 @@ impl<'a> ConsumerControlConfig<'a> {
 impl<'a> Default for ConsumerControlConfig<'a> {
     #[must_use]
     fn default() -> Self {
-        Self::new(
-            unwrap!(
-                unwrap!(InterfaceBuilder::new(MULTIPLE_CODE_REPORT_DESCRIPTOR))
-                    .description("Consumer Control")
-                    .in_endpoint(50.millis())
-            )
-            .without_out_endpoint()
-            .build(),
-        )
+        Self::new(unwrap!(unwrap!(unwrap!(InterfaceBuilder::new(
+            MULTIPLE_CODE_REPORT_DESCRIPTOR
+        ))
+        .description("Consumer Control")
+        .in_endpoint(50.millis()))
+        .without_out_endpoint()
+        .build()))
     }
 }
 
@@ -192,15 +190,13 @@ impl<'a> ConsumerControlFixedConfig<'a> {
 impl<'a> Default for ConsumerControlFixedConfig<'a> {
     #[must_use]
     fn default() -> Self {
-        Self::new(
-            unwrap!(
-                unwrap!(InterfaceBuilder::new(FIXED_FUNCTION_REPORT_DESCRIPTOR))
-                    .description("Consumer Control")
-                    .in_endpoint(50.millis())
-            )
-            .without_out_endpoint()
-            .build(),
-        )
+        Self::new(unwrap!(unwrap!(unwrap!(InterfaceBuilder::new(
+            FIXED_FUNCTION_REPORT_DESCRIPTOR
+        ))
+        .description("Consumer Control")
+        .in_endpoint(50.millis()))
+        .without_out_endpoint()
+        .build()))
     }
 }
 
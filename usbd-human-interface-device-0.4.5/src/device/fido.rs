@@ -80,15 +80,13 @@ pub struct RawFidoConfig<'a> {
 impl<'a> Default for RawFidoConfig<'a> {
     #[must_use]
     fn default() -> Self {
-        Self::new(
-            unwrap!(
-                unwrap!(unwrap!(InterfaceBuilder::new(FIDO_REPORT_DESCRIPTOR))
-                    .description("U2F Token")
-                    .in_endpoint(5.millis()))
-                .with_out_endpoint(5.millis())
-            )
-            .build(),
-        )
+        Self::new(unwrap!(unwrap!(unwrap!(unwrap!(InterfaceBuilder::new(
+            FIDO_REPORT_DESCRIPTOR
+        ))
+        .description("U2F Token")
+        .in_endpoint(5.millis()))
+        .with_out_endpoint(5.millis()))
+        .build()))
     }
 }
 
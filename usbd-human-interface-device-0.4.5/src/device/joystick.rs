@@ -1,53 +1,171 @@
 //!HID joystick
+use crate::page::Desktop;
+use crate::private::Sealed;
+use crate::report_descriptor::builder::{ReportDescriptorBuf, ReportDescriptorBuilder};
+use crate::report_descriptor::fields::write_bits;
+use crate::report_descriptor::{Collection, HidReport, MainFlags, Usage, UsagePage};
 use crate::usb_class::prelude::*;
+use crate::usb_class::{BuilderResult, UsbHidBuilderError};
 use core::default::Default;
+use core::marker::PhantomData;
 use fugit::ExtU32;
+use heapless::Vec;
 use packed_struct::prelude::*;
 use usb_device::bus::UsbBus;
 use usb_device::class_prelude::UsbBusAllocator;
 
-#[rustfmt::skip]
-pub const DEFAULT_JOYSTICK_DESCRIPTOR: &[u8] = &[
-    0x05, 0x01, // Usage Page (Generic Desktop)         5,   1
-    0x09, 0x04, // Usage (Joystick)                     9,   4
-    0xa1, 0x01, // Collection (Application)             161, 1
-    0x09, 0x01, //   Usage Page (Pointer)               9,   1
-    0xa1, 0x00, //   Collection (Physical)              161, 0
-    0x09, 0x30, //     Usage (X)                        9,   48
-    0x09, 0x31, //     Usage (Y)                        9,   49
-    0x09, 0x32, //     Usage (Z)
-    0x15, 0x00, //     Logical Minimum (0)              21,  0
-    0x26, 0x00, 0x04, // Logical Maximum (1024)        38,  0,  16
-    0x75, 0x10, //     Report Size (16)                 117, 16
-    0x95, 0x03, //     Report Count (3)                 149, 3
-    0x81, 0x02, //     Input (Data, Variable, Absolute) 129, 2
-    0xc0,       //   End Collection                     192
-    0x05, 0x09, //   Usage Page (Button)                5,   9
-    0x19, 0x01, //   Usage Minimum (1)                  25,  1
-    0x29, 0x08, //   Usage Maximum (8)                  41,  8
-    0x15, 0x00, //   Logical Minimum (0)                21,  0
-    0x25, 0x01, //   Logical Maximum (1)                37,  1
-    0x75, 0x01, //   Report Size (1)                    117, 1
-    0x95, 0x08, //   Report Count (8)                   149, 8
-    0x81, 0x02, //   Input (Data, Variable, Absolute)   129, 2
-    0xc0        // End Collection                       192
-];
-
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
-#[packed_struct(endian = "lsb", size_bytes = "7")]
+/// Three 16 bit axes with a range of `0..=1024` and eight buttons
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, HidReport)]
+#[hid(usage = Desktop::Joystick)]
 pub struct JoystickReport {
-    #[packed_field]
+    #[hid(usage = Desktop::X, logical_max = 1024, collection = Physical(Desktop::Pointer))]
     pub x: u16,
-    #[packed_field]
+    #[hid(usage = Desktop::Y, logical_max = 1024, collection = Physical(Desktop::Pointer))]
     pub y: u16,
-    #[packed_field]
+    #[hid(usage = Desktop::Z, logical_max = 1024, collection = Physical(Desktop::Pointer))]
     pub z: u16,
-    #[packed_field]
+    #[hid(usage_min = Button(1), usage_max = Button(8), count = 8)]
     pub buttons: u8,
 }
 
+pub const DEFAULT_JOYSTICK_DESCRIPTOR: &[u8] = JoystickReport::DESCRIPTOR;
+
+/// Axes of a [`JoystickLayout`] at most
+pub const MAX_JOYSTICK_AXES: usize = 8;
+/// Buttons of a [`JoystickLayout`] at most
+pub const MAX_JOYSTICK_BUTTONS: u8 = 64;
+/// Bytes of the report of the largest [`JoystickLayout`]
+pub const JOYSTICK_REPORT_CAPACITY: usize = 32;
+/// Bytes of the descriptor of the largest [`JoystickLayout`]
+pub const JOYSTICK_DESCRIPTOR_CAPACITY: usize = 96;
+
+/// Report descriptor generated by [`JoystickLayout::write_descriptor`]
+pub type JoystickDescriptor = Vec<u8, JOYSTICK_DESCRIPTOR_CAPACITY>;
+/// Report packed by [`JoystickLayout::pack`]
+pub type JoystickReportBuffer = Vec<u8, JOYSTICK_REPORT_CAPACITY>;
+
+/// Shape of a joystick report chosen at runtime
+///
+/// Axes are reported first, in order, each `axis_bits` wide with a logical range of
+/// `0..=2^axis_bits - 1`. Buttons follow at one bit each, and the report is padded to a whole
+/// byte.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct JoystickLayout {
+    axes: Vec<Desktop, MAX_JOYSTICK_AXES>,
+    axis_bits: u8,
+    buttons: u8,
+}
+
+impl JoystickLayout {
+    pub fn new(axes: &[Desktop], axis_bits: u8, buttons: u8) -> BuilderResult<Self> {
+        if !(1..=16).contains(&axis_bits) || buttons > MAX_JOYSTICK_BUTTONS {
+            return Err(UsbHidBuilderError::ValueOverflow);
+        }
+
+        Ok(Self {
+            axes: Vec::from_slice(axes).map_err(|_| UsbHidBuilderError::SliceLengthOverflow)?,
+            axis_bits,
+            buttons,
+        })
+    }
+
+    #[must_use]
+    pub fn axes(&self) -> &[Desktop] {
+        &self.axes
+    }
+
+    #[must_use]
+    pub fn axis_max(&self) -> u16 {
+        u16::MAX >> (16 - self.axis_bits)
+    }
+
+    #[must_use]
+    pub fn buttons(&self) -> u8 {
+        self.buttons
+    }
+
+    fn data_bits(&self) -> usize {
+        self.axes.len() * usize::from(self.axis_bits) + usize::from(self.buttons)
+    }
+
+    /// Report length in bytes
+    #[must_use]
+    pub fn report_len(&self) -> usize {
+        self.data_bits().div_ceil(8)
+    }
+
+    /// Replaces the contents of `descriptor` with the report descriptor of this layout
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn write_descriptor(&self, descriptor: &mut JoystickDescriptor) {
+        let mut builder = ReportDescriptorBuilder::<JOYSTICK_DESCRIPTOR_CAPACITY>::new()
+            .usage_page(UsagePage::Desktop)
+            .usage(Usage::Desktop(Desktop::Joystick))
+            .collection(Collection::Application)
+            .usage(Usage::Desktop(Desktop::Pointer))
+            .collection(Collection::Physical);
+        for &axis in &self.axes {
+            builder = builder.usage(Usage::Desktop(axis));
+        }
+        builder = builder
+            .logical_minimum(0)
+            .logical_maximum(self.axis_max().into())
+            .report_size(self.axis_bits.into())
+            .report_count(self.axes.len() as u32)
+            .input(MainFlags::VARIABLE)
+            .end_collection();
+        if self.buttons > 0 {
+            builder = builder
+                .usage_page(UsagePage::Button)
+                .usage_minimum(Usage::Button(1))
+                .usage_maximum(Usage::Button(self.buttons.into()))
+                .logical_minimum(0)
+                .logical_maximum(1)
+                .report_size(1)
+                .report_count(self.buttons.into())
+                .input(MainFlags::VARIABLE);
+        }
+        let padding = self.report_len() * 8 - self.data_bits();
+        if padding > 0 {
+            builder = builder.padding(padding as u32);
+        }
+
+        let built = unwrap!(builder.end_collection().build());
+        descriptor.clear();
+        unwrap!(descriptor.extend_from_slice(built.as_bytes()));
+    }
+
+    /// Packs axis values, in layout order, and buttons, bit `n` is button `n + 1`
+    ///
+    /// Axis values are clamped to [`JoystickLayout::axis_max`]
+    pub fn pack(&self, axes: &[u16], buttons: u64) -> Result<JoystickReportBuffer, UsbHidError> {
+        if axes.len() != self.axes.len() {
+            error!(
+                "Error packing joystick report, expected {} axes got {}",
+                self.axes.len(),
+                axes.len()
+            );
+            return Err(UsbHidError::SerializationError);
+        }
+
+        let mut report = JoystickReportBuffer::new();
+        unwrap!(report.resize_default(self.report_len()));
+        let bits = usize::from(self.axis_bits);
+        for (i, &value) in axes.iter().enumerate() {
+            write_bits(
+                &mut report,
+                i * bits,
+                bits,
+                value.min(self.axis_max()).into(),
+            );
+        }
+        write_bits(&mut report, axes.len() * bits, self.buttons.into(), buttons);
+        Ok(report)
+    }
+}
+
 pub struct Joystick<'a, B: UsbBus> {
-    interface: Interface<'a, B, InBytes8, OutNone, ReportSingle>,
+    interface: Interface<'a, B, InBytes32, OutNone, ReportSingle>,
+    layout: Option<JoystickLayout>,
 }
 
 impl<'a, B: UsbBus> Joystick<'a, B> {
@@ -61,10 +179,27 @@ impl<'a, B: UsbBus> Joystick<'a, B> {
             .map(|_| ())
             .map_err(UsbHidError::from)
     }
+
+    /// Writes a report of the layout the device was configured with, see
+    /// [`JoystickLayout::pack`]
+    ///
+    /// Fails with [`UsbHidError::SerializationError`] for devices configured with a fixed
+    /// descriptor
+    pub fn write_values(&mut self, axes: &[u16], buttons: u64) -> Result<(), UsbHidError> {
+        let Some(layout) = &self.layout else {
+            error!("Joystick has no layout to pack values with");
+            return Err(UsbHidError::SerializationError);
+        };
+        let data = layout.pack(axes, buttons)?;
+        self.interface
+            .write_report(&data)
+            .map(|_| ())
+            .map_err(UsbHidError::from)
+    }
 }
 
 impl<'a, B: UsbBus> DeviceClass<'a> for Joystick<'a, B> {
-    type I = Interface<'a, B, InBytes8, OutNone, ReportSingle>;
+    type I = Interface<'a, B, InBytes32, OutNone, ReportSingle>;
 
     fn interface(&mut self) -> &mut Self::I {
         &mut self.interface
@@ -78,40 +213,66 @@ impl<'a, B: UsbBus> DeviceClass<'a> for Joystick<'a, B> {
 }
 
 pub struct JoystickConfig<'a> {
-    interface: InterfaceConfig<'a, InBytes8, OutNone, ReportSingle>,
+    interface: InterfaceConfig<'a, InBytes32, OutNone, ReportSingle>,
+    layout: Option<JoystickLayout>,
 }
 
 impl<'a> JoystickConfig<'a> {
+    /// Joystick reporting `layout`, its report descriptor is generated into `descriptor`
+    #[must_use]
+    pub fn new_with_layout(layout: JoystickLayout, descriptor: &'a mut JoystickDescriptor) -> Self {
+        layout.write_descriptor(descriptor);
+        let descriptor: &'a JoystickDescriptor = descriptor;
+        Self {
+            layout: Some(layout),
+            ..Self::new_with_descriptor(descriptor)
+        }
+    }
+
+    /// Joystick with a fixed report descriptor, reports are written with
+    /// [`Joystick::write_report`] or directly to the interface
     pub fn new_with_descriptor(descriptior: &'a [u8]) -> Self {
-         Self::new(
-            unwrap!(unwrap!(InterfaceBuilder::new(descriptior))
-                .boot_device(InterfaceProtocol::None)
-                .description("Joystick")
-                .in_endpoint(10.millis()))
-            .without_out_endpoint()
-            .build(),
-        )
+        Self::new_with_interface(unwrap!(unwrap!(unwrap!(InterfaceBuilder::new(
+            descriptior
+        ))
+        .boot_device(InterfaceProtocol::None)
+        .description("Joystick")
+        .in_endpoint(10.millis()))
+        .without_out_endpoint()
+        .build()))
     }
 }
 
 impl<'a> Default for JoystickConfig<'a> {
     #[must_use]
     fn default() -> Self {
-        Self::new(
-            unwrap!(unwrap!(InterfaceBuilder::new(DEFAULT_JOYSTICK_DESCRIPTOR))
-                .boot_device(InterfaceProtocol::None)
-                .description("Joystick")
-                .in_endpoint(10.millis()))
-            .without_out_endpoint()
-            .build(),
-        )
+        Self::new_with_interface(unwrap!(unwrap!(unwrap!(InterfaceBuilder::new(
+            DEFAULT_JOYSTICK_DESCRIPTOR
+        ))
+        .boot_device(InterfaceProtocol::None)
+        .description("Joystick")
+        .in_endpoint(10.millis()))
+        .without_out_endpoint()
+        .build()))
     }
 }
 
 impl<'a> JoystickConfig<'a> {
+    /// Joystick with reports of up to 8 bytes
     #[must_use]
     pub fn new(interface: InterfaceConfig<'a, InBytes8, OutNone, ReportSingle>) -> Self {
-        Self { interface }
+        Self::new_with_interface(interface.with_in_size())
+    }
+
+    /// Like [`JoystickConfig::new`], for reports of up to [`JOYSTICK_REPORT_CAPACITY`] bytes
+    #[must_use]
+    pub fn new_with_interface(
+        interface: InterfaceConfig<'a, InBytes32, OutNone, ReportSingle>,
+    ) -> Self {
+        Self {
+            interface,
+            layout: None,
+        }
     }
 }
 
@@ -121,6 +282,372 @@ impl<'a, B: UsbBus + 'a> UsbAllocatable<'a, B> for JoystickConfig<'a> {
     fn allocate(self, usb_alloc: &'a UsbBusAllocator<B>) -> Self::Allocated {
         Self::Allocated {
             interface: Interface::new(usb_alloc, self.interface),
+            layout: self.layout,
         }
     }
 }
+
+/// Hat switches of a [`GenericJoystick`] at most
+pub const MAX_JOYSTICK_HATS: usize = 4;
+/// Axis usages of a [`GenericJoystick`], in report order
+pub const GENERIC_JOYSTICK_AXES: [Desktop; MAX_JOYSTICK_AXES] = [
+    Desktop::X,
+    Desktop::Y,
+    Desktop::Z,
+    Desktop::Rx,
+    Desktop::Ry,
+    Desktop::Rz,
+    Desktop::Slider,
+    Desktop::Dial,
+];
+
+/// Hat switch position, clockwise from up
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[repr(u8)]
+pub enum HatDirection {
+    Up = 0,
+    UpRight = 1,
+    Right = 2,
+    DownRight = 3,
+    Down = 4,
+    DownLeft = 5,
+    Left = 6,
+    UpLeft = 7,
+}
+
+/// Reported for a centred hat, outside the logical range so the host reads it as null
+const HAT_CENTERED: u64 = 0x08;
+/// Unit of hat switch positions, English rotation in degrees
+const UNIT_DEGREES: u32 = 0x14;
+
+/// Axis value of a [`GenericJoystick`], `u16` for unsigned axes and `i16` for signed axes
+pub trait JoystickAxis: Sealed + Copy + Default {
+    const LOGICAL_MIN: i32;
+    const LOGICAL_MAX: i32;
+    fn report_bytes(self) -> [u8; 2];
+}
+
+impl JoystickAxis for u16 {
+    const LOGICAL_MIN: i32 = 0;
+    const LOGICAL_MAX: i32 = 0xffff;
+
+    fn report_bytes(self) -> [u8; 2] {
+        self.to_le_bytes()
+    }
+}
+
+impl JoystickAxis for i16 {
+    const LOGICAL_MIN: i32 = -0x8000;
+    const LOGICAL_MAX: i32 = 0x7fff;
+
+    fn report_bytes(self) -> [u8; 2] {
+        self.to_le_bytes()
+    }
+}
+
+/// Report of a [`GenericJoystick`]
+///
+/// The report holds 16 bit axes with the usages of [`GENERIC_JOYSTICK_AXES`], followed by 4 bit
+/// hat switches and 1 bit buttons, padded to a whole byte. Its descriptor and length are
+/// derived at compile time from the parameters.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct GenericJoystickReport<A, const AXES: usize, const BUTTONS: usize, const HATS: usize> {
+    pub axes: [A; AXES],
+    /// `None` is centred
+    pub hats: [Option<HatDirection>; HATS],
+    /// Bit `n` is button `n + 1`
+    pub buttons: u64,
+}
+
+impl<A: JoystickAxis, const AXES: usize, const BUTTONS: usize, const HATS: usize> Default
+    for GenericJoystickReport<A, AXES, BUTTONS, HATS>
+{
+    fn default() -> Self {
+        Self {
+            axes: [A::default(); AXES],
+            hats: [None; HATS],
+            buttons: 0,
+        }
+    }
+}
+
+impl<A: JoystickAxis, const AXES: usize, const BUTTONS: usize, const HATS: usize>
+    GenericJoystickReport<A, AXES, BUTTONS, HATS>
+{
+    /// Fails to compile for joysticks beyond the limits of the report buffer
+    // `core::assert!`, the crate's `assert!` logs with defmt, which can't run in a const
+    const LIMITS: () = core::assert!(
+        AXES <= MAX_JOYSTICK_AXES
+            && BUTTONS <= MAX_JOYSTICK_BUTTONS as usize
+            && HATS <= MAX_JOYSTICK_HATS
+            && AXES + BUTTONS + HATS > 0,
+        "joystick axes, buttons or hats out of range"
+    );
+
+    /// Report length in bytes
+    pub const REPORT_LEN: usize = AXES * 2 + (HATS * 4 + BUTTONS).div_ceil(8);
+
+    const DESCRIPTOR_BUFFER: ReportDescriptorBuf<JOYSTICK_DESCRIPTOR_CAPACITY> = Self::descriptor();
+    pub const DESCRIPTOR: &'static [u8] = Self::DESCRIPTOR_BUFFER.as_bytes();
+
+    #[allow(clippy::cast_possible_truncation)]
+    const fn descriptor() -> ReportDescriptorBuf<JOYSTICK_DESCRIPTOR_CAPACITY> {
+        let mut builder = ReportDescriptorBuilder::new()
+            .usage_page(UsagePage::Desktop)
+            .usage(Usage::Desktop(Desktop::Joystick))
+            .collection(Collection::Application);
+        if AXES > 0 {
+            builder = builder
+                .usage(Usage::Desktop(Desktop::Pointer))
+                .collection(Collection::Physical);
+            let mut i = 0;
+            while i < AXES {
+                builder = builder.usage(Usage::Desktop(GENERIC_JOYSTICK_AXES[i]));
+                i += 1;
+            }
+            builder = builder
+                .logical_minimum(A::LOGICAL_MIN)
+                .logical_maximum(A::LOGICAL_MAX)
+                .report_size(16)
+                .report_count(AXES as u32)
+                .input(MainFlags::VARIABLE)
+                .end_collection();
+        }
+        if HATS > 0 {
+            builder = builder
+                .usage(Usage::Desktop(Desktop::HatSwitch))
+                .logical_minimum(0)
+                .logical_maximum(7)
+                .physical_minimum(0)
+                .physical_maximum(315)
+                .unit(UNIT_DEGREES)
+                .report_size(4)
+                .report_count(HATS as u32)
+                .input(MainFlags::VARIABLE.union(MainFlags::NULL_STATE))
+                .unit(0)
+                .physical_maximum(0);
+        }
+        if BUTTONS > 0 {
+            builder = builder
+                .usage_page(UsagePage::Button)
+                .usage_minimum(Usage::Button(1))
+                .usage_maximum(Usage::Button(BUTTONS as u16))
+                .logical_minimum(0)
+                .logical_maximum(1)
+                .report_size(1)
+                .report_count(BUTTONS as u32)
+                .input(MainFlags::VARIABLE);
+        }
+        let padding = (8 - (HATS * 4 + BUTTONS) % 8) % 8;
+        if padding > 0 {
+            builder = builder.padding(padding as u32);
+        }
+        builder.end_collection().finish()
+    }
+
+    #[must_use]
+    pub fn pack(&self) -> JoystickReportBuffer {
+        let mut report = JoystickReportBuffer::new();
+        for axis in self.axes {
+            unwrap!(report.extend_from_slice(&axis.report_bytes()));
+        }
+        unwrap!(report.resize_default(Self::REPORT_LEN));
+        for (i, hat) in self.hats.iter().enumerate() {
+            let value = hat.map_or(HAT_CENTERED, |hat| hat as u64);
+            write_bits(&mut report, AXES * 16 + i * 4, 4, value);
+        }
+        write_bits(&mut report, AXES * 16 + HATS * 4, BUTTONS, self.buttons);
+        report
+    }
+}
+
+/// Joystick with `AXES` axes of type `A`, `BUTTONS` buttons and `HATS` hat switches
+pub struct GenericJoystick<
+    'a,
+    B: UsbBus,
+    A,
+    const AXES: usize,
+    const BUTTONS: usize,
+    const HATS: usize,
+> {
+    interface: Interface<'a, B, InBytes32, OutNone, ReportSingle>,
+    marker: PhantomData<A>,
+}
+
+impl<B: UsbBus, A: JoystickAxis, const AXES: usize, const BUTTONS: usize, const HATS: usize>
+    GenericJoystick<'_, B, A, AXES, BUTTONS, HATS>
+{
+    pub fn write_report(
+        &mut self,
+        report: &GenericJoystickReport<A, AXES, BUTTONS, HATS>,
+    ) -> Result<(), UsbHidError> {
+        self.interface
+            .write_report(&report.pack())
+            .map(|_| ())
+            .map_err(UsbHidError::from)
+    }
+}
+
+impl<'a, B: UsbBus, A, const AXES: usize, const BUTTONS: usize, const HATS: usize> DeviceClass<'a>
+    for GenericJoystick<'a, B, A, AXES, BUTTONS, HATS>
+{
+    type I = Interface<'a, B, InBytes32, OutNone, ReportSingle>;
+
+    fn interface(&mut self) -> &mut Self::I {
+        &mut self.interface
+    }
+
+    fn reset(&mut self) {}
+
+    fn tick(&mut self) -> Result<(), UsbHidError> {
+        Ok(())
+    }
+}
+
+pub struct GenericJoystickConfig<'a, A, const AXES: usize, const BUTTONS: usize, const HATS: usize>
+{
+    interface: InterfaceConfig<'a, InBytes32, OutNone, ReportSingle>,
+    marker: PhantomData<A>,
+}
+
+impl<A: JoystickAxis, const AXES: usize, const BUTTONS: usize, const HATS: usize> Default
+    for GenericJoystickConfig<'_, A, AXES, BUTTONS, HATS>
+{
+    #[allow(clippy::cast_possible_truncation)]
+    fn default() -> Self {
+        let () = GenericJoystickReport::<A, AXES, BUTTONS, HATS>::LIMITS;
+        let report_len = GenericJoystickReport::<A, AXES, BUTTONS, HATS>::REPORT_LEN as u16;
+        Self::new(unwrap!(unwrap!(unwrap!(unwrap!(
+            InterfaceBuilder::with_static_descriptor(
+                GenericJoystickReport::<A, AXES, BUTTONS, HATS>::DESCRIPTOR
+            )
+        )
+        .boot_device(InterfaceProtocol::None)
+        .description("Joystick")
+        .in_endpoint(10.millis()))
+        .in_endpoint_size(report_len))
+        .without_out_endpoint()
+        .build()))
+    }
+}
+
+impl<'a, A, const AXES: usize, const BUTTONS: usize, const HATS: usize>
+    GenericJoystickConfig<'a, A, AXES, BUTTONS, HATS>
+{
+    #[must_use]
+    pub fn new(interface: InterfaceConfig<'a, InBytes32, OutNone, ReportSingle>) -> Self {
+        Self {
+            interface,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, B: UsbBus + 'a, A, const AXES: usize, const BUTTONS: usize, const HATS: usize>
+    UsbAllocatable<'a, B> for GenericJoystickConfig<'a, A, AXES, BUTTONS, HATS>
+{
+    type Allocated = GenericJoystick<'a, B, A, AXES, BUTTONS, HATS>;
+
+    fn allocate(self, usb_alloc: &'a UsbBusAllocator<B>) -> Self::Allocated {
+        Self::Allocated {
+            interface: Interface::new(usb_alloc, self.interface),
+            marker: PhantomData,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use crate::device::joystick::{
+        GenericJoystickReport, HatDirection, JoystickDescriptor, JoystickLayout, JoystickReport,
+        DEFAULT_JOYSTICK_DESCRIPTOR,
+    };
+    use crate::page::Desktop;
+    use packed_struct::PackedStruct;
+
+    #[test]
+    fn default_descriptor() {
+        #[rustfmt::skip]
+        let expected: &[u8] = &[
+            0x05, 0x01, 0x09, 0x04, 0xa1, 0x01, 0x09, 0x01, 0xa1, 0x00,
+            0x09, 0x30, 0x09, 0x31, 0x09, 0x32,
+            0x15, 0x00, 0x26, 0x00, 0x04, 0x75, 0x10, 0x95, 0x03, 0x81, 0x02, 0xc0,
+            0x05, 0x09, 0x19, 0x01, 0x29, 0x08, 0x15, 0x00, 0x25, 0x01,
+            0x75, 0x01, 0x95, 0x08, 0x81, 0x02,
+            0xc0,
+        ];
+        assert_eq!(DEFAULT_JOYSTICK_DESCRIPTOR, expected);
+    }
+
+    #[test]
+    fn default_report_pack() {
+        let report = JoystickReport {
+            x: 1024,
+            y: 1,
+            z: 0x0302,
+            buttons: 0b1000_0001,
+        };
+        let packed = report.pack().unwrap();
+        assert_eq!(packed, [0x00, 0x04, 0x01, 0x00, 0x02, 0x03, 0x81]);
+        assert_eq!(JoystickReport::unpack(&packed).unwrap(), report);
+    }
+
+    #[test]
+    fn layout_descriptor() {
+        let layout = JoystickLayout::new(&[Desktop::X, Desktop::Y, Desktop::Z], 10, 6).unwrap();
+        let mut descriptor = JoystickDescriptor::new();
+        layout.write_descriptor(&mut descriptor);
+
+        #[rustfmt::skip]
+        let expected: &[u8] = &[
+            0x05, 0x01, 0x09, 0x04, 0xa1, 0x01, 0x09, 0x01, 0xa1, 0x00,
+            0x09, 0x30, 0x09, 0x31, 0x09, 0x32,
+            0x15, 0x00, 0x26, 0xff, 0x03, 0x75, 0x0a, 0x95, 0x03, 0x81, 0x02, 0xc0,
+            0x05, 0x09, 0x19, 0x01, 0x29, 0x06, 0x15, 0x00, 0x25, 0x01,
+            0x75, 0x01, 0x95, 0x06, 0x81, 0x02,
+            0x75, 0x04, 0x95, 0x01, 0x81, 0x03,
+            0xc0,
+        ];
+        assert_eq!(&descriptor[..], expected);
+    }
+
+    #[test]
+    fn layout_pack() {
+        let layout = JoystickLayout::new(&[Desktop::X, Desktop::Y, Desktop::Z], 10, 6).unwrap();
+        let report = layout.pack(&[0xffff, 0, 0x155], 0b10_0001).unwrap();
+        assert_eq!(&report[..], &[0xff, 0x03, 0x50, 0x55, 0x08]);
+        assert!(layout.pack(&[0, 0], 0).is_err());
+    }
+
+    type SignedReport = GenericJoystickReport<i16, 2, 4, 1>;
+
+    #[test]
+    fn generic_descriptor() {
+        #[rustfmt::skip]
+        let expected: &[u8] = &[
+            0x05, 0x01, 0x09, 0x04, 0xa1, 0x01, 0x09, 0x01, 0xa1, 0x00,
+            0x09, 0x30, 0x09, 0x31,
+            0x16, 0x00, 0x80, 0x26, 0xff, 0x7f, 0x75, 0x10, 0x95, 0x02, 0x81, 0x02, 0xc0,
+            0x09, 0x39, 0x15, 0x00, 0x25, 0x07, 0x35, 0x00, 0x46, 0x3b, 0x01, 0x65, 0x14,
+            0x75, 0x04, 0x95, 0x01, 0x81, 0x42, 0x65, 0x00, 0x45, 0x00,
+            0x05, 0x09, 0x19, 0x01, 0x29, 0x04, 0x15, 0x00, 0x25, 0x01,
+            0x75, 0x01, 0x95, 0x04, 0x81, 0x02,
+            0xc0,
+        ];
+        assert_eq!(SignedReport::DESCRIPTOR, expected);
+    }
+
+    #[test]
+    fn generic_report_pack() {
+        let report = SignedReport {
+            axes: [-1, 0x1234],
+            hats: [Some(HatDirection::Left)],
+            buttons: 0b1001,
+        };
+        assert_eq!(SignedReport::REPORT_LEN, 5);
+        assert_eq!(&report.pack()[..], &[0xff, 0xff, 0x34, 0x12, 0x96]);
+        assert_eq!(SignedReport::default().pack()[4], 0x08);
+    }
+}
@@ -64,19 +64,19 @@ pub struct BootKeyboardConfig<'a> {
 impl<'a> Default for BootKeyboardConfig<'a> {
     #[must_use]
     fn default() -> Self {
-        Self::new(ManagedIdleInterfaceConfig::new(
-            unwrap!(unwrap!(unwrap!(unwrap!(InterfaceBuilder::new(
-                BOOT_KEYBOARD_REPORT_DESCRIPTOR
-            ))
-            .boot_device(InterfaceProtocol::Keyboard)
-            .description("Keyboard")
-            .idle_default(500.millis()))
-            .in_endpoint(10.millis()))
-            //.without_out_endpoint()
-            //Shouldn't require a dedicated out endpoint, but leds are flaky without it
-            .with_out_endpoint(100.millis()))
-            .build(),
-        ))
+        Self::new(ManagedIdleInterfaceConfig::new(unwrap!(unwrap!(unwrap!(
+            unwrap!(
+                unwrap!(InterfaceBuilder::new(BOOT_KEYBOARD_REPORT_DESCRIPTOR))
+                    .boot_device(InterfaceProtocol::Keyboard)
+                    .description("Keyboard")
+                    .idle_default(500.millis())
+            )
+            .in_endpoint(10.millis())
+        )
+        //.without_out_endpoint()
+        //Shouldn't require a dedicated out endpoint, but leds are flaky without it
+        .with_out_endpoint(100.millis()))
+        .build())))
     }
 }
 
@@ -428,17 +428,17 @@ pub struct NKROBootKeyboardConfig<'a> {
 impl<'a> Default for NKROBootKeyboardConfig<'a> {
     #[must_use]
     fn default() -> Self {
-        Self::new(ManagedIdleInterfaceConfig::new(
-            unwrap!(unwrap!(unwrap!(unwrap!(InterfaceBuilder::new(
-                NKRO_BOOT_KEYBOARD_REPORT_DESCRIPTOR
-            ))
-            .description("NKRO Keyboard")
-            .boot_device(InterfaceProtocol::Keyboard)
-            .idle_default(500.millis()))
-            .in_endpoint(10.millis()))
-            .with_out_endpoint(100.millis()))
-            .build(),
-        ))
+        Self::new(ManagedIdleInterfaceConfig::new(unwrap!(unwrap!(unwrap!(
+            unwrap!(
+                unwrap!(InterfaceBuilder::new(NKRO_BOOT_KEYBOARD_REPORT_DESCRIPTOR))
+                    .description("NKRO Keyboard")
+                    .boot_device(InterfaceProtocol::Keyboard)
+                    .idle_default(500.millis())
+            )
+            .in_endpoint(10.millis())
+        )
+        .with_out_endpoint(100.millis()))
+        .build())))
     }
 }
 
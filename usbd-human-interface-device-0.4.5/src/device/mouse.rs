@@ -203,14 +203,14 @@ impl<'a> BootMouseConfig<'a> {
 impl<'a> Default for BootMouseConfig<'a> {
     #[must_use]
     fn default() -> Self {
-        Self::new(
-            unwrap!(unwrap!(InterfaceBuilder::new(BOOT_MOUSE_REPORT_DESCRIPTOR))
-                .boot_device(InterfaceProtocol::Mouse)
-                .description("Mouse")
-                .in_endpoint(10.millis()))
-            .without_out_endpoint()
-            .build(),
-        )
+        Self::new(unwrap!(unwrap!(unwrap!(InterfaceBuilder::new(
+            BOOT_MOUSE_REPORT_DESCRIPTOR
+        ))
+        .boot_device(InterfaceProtocol::Mouse)
+        .description("Mouse")
+        .in_endpoint(10.millis()))
+        .without_out_endpoint()
+        .build()))
     }
 }
 
@@ -268,16 +268,14 @@ impl<'a> WheelMouseConfig<'a> {
 impl<'a> Default for WheelMouseConfig<'a> {
     #[must_use]
     fn default() -> Self {
-        WheelMouseConfig::new(
-            unwrap!(
-                unwrap!(InterfaceBuilder::new(WHEEL_MOUSE_REPORT_DESCRIPTOR))
-                    .boot_device(InterfaceProtocol::Mouse)
-                    .description("Wheel Mouse")
-                    .in_endpoint(10.millis())
-            )
-            .without_out_endpoint()
-            .build(),
-        )
+        WheelMouseConfig::new(unwrap!(unwrap!(unwrap!(InterfaceBuilder::new(
+            WHEEL_MOUSE_REPORT_DESCRIPTOR
+        ))
+        .boot_device(InterfaceProtocol::Mouse)
+        .description("Wheel Mouse")
+        .in_endpoint(10.millis()))
+        .without_out_endpoint()
+        .build()))
     }
 }
 
@@ -336,15 +334,13 @@ impl<'a> AbsoluteWheelMouseConfig<'a> {
 impl<'a> Default for AbsoluteWheelMouseConfig<'a> {
     #[must_use]
     fn default() -> Self {
-        AbsoluteWheelMouseConfig::new(
-            unwrap!(unwrap!(InterfaceBuilder::new(
-                ABSOLUTE_WHEEL_MOUSE_REPORT_DESCRIPTOR
-            ))
-            .description("Absolute Wheel Mouse")
-            .in_endpoint(10.millis()))
-            .without_out_endpoint()
-            .build(),
-        )
+        AbsoluteWheelMouseConfig::new(unwrap!(unwrap!(unwrap!(InterfaceBuilder::new(
+            ABSOLUTE_WHEEL_MOUSE_REPORT_DESCRIPTOR
+        ))
+        .description("Absolute Wheel Mouse")
+        .in_endpoint(10.millis()))
+        .without_out_endpoint()
+        .build()))
     }
 }
 
@@ -1,10 +1,11 @@
 //! Human Interface Device Interfaces
 use crate::descriptor::{
-    DescriptorType, HidProtocol, InterfaceProtocol, InterfaceSubClass, COUNTRY_CODE_NOT_SUPPORTED,
-    SPEC_VERSION_1_11, USB_CLASS_HID,
+    DescriptorType, HidProtocol, InterfaceProtocol, InterfaceSubClass, ReportType,
+    COUNTRY_CODE_NOT_SUPPORTED, SPEC_VERSION_1_11, USB_CLASS_HID,
 };
 use crate::device::DeviceClass;
 use crate::private::Sealed;
+use crate::report_descriptor::parser::{ParsedReportDescriptor, ReportSizes};
 use crate::usb_class::{BuilderResult, UsbHidBuilderError};
 use crate::UsbHidError;
 use core::marker::PhantomData;
@@ -67,9 +68,18 @@ pub trait InterfaceClass<'a> {
     fn write_descriptors(&self, writer: &mut DescriptorWriter) -> usb_device::Result<()>;
     fn get_string(&self, index: StringIndex, _lang_id: u16) -> Option<&'a str>;
     fn reset(&mut self);
-    fn set_report(&mut self, data: &[u8]) -> usb_device::Result<()>;
-    fn get_report(&self, data: &mut [u8]) -> usb_device::Result<usize>;
-    fn get_report_ack(&mut self) -> usb_device::Result<()>;
+    fn set_report(
+        &mut self,
+        report_type: ReportType,
+        report_id: u8,
+        data: &[u8],
+    ) -> usb_device::Result<()>;
+    fn get_report(
+        &self,
+        report_type: ReportType,
+        report_id: u8,
+        data: &mut [u8],
+    ) -> usb_device::Result<usize>;
     fn set_idle(&mut self, report_id: u8, value: u8);
     fn get_idle(&self, report_id: u8) -> u8;
     fn set_protocol(&mut self, protocol: HidProtocol);
@@ -239,6 +249,105 @@ option_block_idle_storage!(Reports32, Block32);
 option_block_idle_storage!(Reports64, Block64);
 option_block_idle_storage!(Reports128, Block128);
 
+/// Latest report of every report ID, set by the device or the host
+pub trait ReportStorage: Default {
+    /// Replaces the report of `report_id`, `changed` marks it for [`ReportStorage::take_changed`]
+    fn write(&mut self, report_id: u8, data: &[u8], changed: bool) -> usb_device::Result<()>;
+    fn read(&self, report_id: u8) -> Option<&[u8]>;
+    /// Report of `report_id`, or of any report ID if `None`, that changed since it was last taken
+    fn take_changed(&mut self, report_id: Option<u8>) -> Option<&[u8]>;
+}
+
+struct StoredReport<T> {
+    report_id: u8,
+    buffer: T,
+    changed: bool,
+}
+
+/// Buffers of up to `N` report IDs
+pub struct ReportsById<T, const N: usize> {
+    reports: Vec<StoredReport<T>, N>,
+}
+
+impl<T, const N: usize> Default for ReportsById<T, N> {
+    fn default() -> Self {
+        Self {
+            reports: Vec::new(),
+        }
+    }
+}
+
+impl<T: ReportBuffer, const N: usize> ReportStorage for ReportsById<T, N> {
+    fn write(&mut self, report_id: u8, data: &[u8], changed: bool) -> usb_device::Result<()> {
+        if data.len() > usize::from(T::CAPACITY) {
+            return Err(UsbError::BufferOverflow);
+        }
+
+        let index = if let Some(index) = self.reports.iter().position(|r| r.report_id == report_id)
+        {
+            index
+        } else {
+            self.reports
+                .push(StoredReport {
+                    report_id,
+                    buffer: T::default(),
+                    changed: false,
+                })
+                .map_err(|_| UsbError::BufferOverflow)?;
+            self.reports.len() - 1
+        };
+
+        let report = &mut self.reports[index];
+        report.buffer.clear();
+        report
+            .buffer
+            .extend_from_slice(data)
+            .map_err(|()| UsbError::BufferOverflow)?;
+        report.changed = changed;
+        Ok(())
+    }
+
+    fn read(&self, report_id: u8) -> Option<&[u8]> {
+        self.reports
+            .iter()
+            .find(|r| r.report_id == report_id)
+            .map(|r| r.buffer.as_ref())
+    }
+
+    fn take_changed(&mut self, report_id: Option<u8>) -> Option<&[u8]> {
+        let report = self
+            .reports
+            .iter_mut()
+            .find(|r| r.changed && report_id.is_none_or(|id| r.report_id == id))?;
+        report.changed = false;
+        Some(report.buffer.as_ref())
+    }
+}
+
+pub trait FeatureSize: Sealed {
+    type Buffer: ReportBuffer;
+}
+pub enum FeatureNone {}
+impl Sealed for FeatureNone {}
+impl FeatureSize for FeatureNone {
+    type Buffer = ();
+}
+
+macro_rules! vec_feature_bytes {
+    ($name: ident, $capacity: literal) => {
+        pub enum $name {}
+        impl Sealed for $name {}
+        impl FeatureSize for $name {
+            type Buffer = Vec<u8, $capacity>;
+        }
+    };
+}
+
+vec_feature_bytes!(FeatureBytes8, 8);
+vec_feature_bytes!(FeatureBytes16, 16);
+vec_feature_bytes!(FeatureBytes32, 32);
+vec_feature_bytes!(FeatureBytes64, 64);
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ReportDescriptor<'a> {
     StaticDescriptor(&'static [u8]),
@@ -246,13 +355,14 @@ pub enum ReportDescriptor<'a> {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct InterfaceConfig<'a, I, O, R>
+pub struct InterfaceConfig<'a, I, O, R, F = FeatureNone, const REPORTS: usize = 1>
 where
     I: InSize,
     O: OutSize,
     R: ReportCount,
+    F: FeatureSize,
 {
-    marker: PhantomData<(I, O, R)>,
+    marker: PhantomData<(I, O, R, F)>,
     report_descriptor: ReportDescriptor<'a>,
     report_descriptor_length: u16,
     description: Option<&'a str>,
@@ -260,47 +370,87 @@ where
     idle_default: u8,
     out_endpoint: Option<EndpointConfig>,
     in_endpoint: EndpointConfig,
+    /// Reports start with their report ID
+    report_ids: bool,
 }
 
-pub struct Interface<'a, B, I, O, R>
+/// Keeps the latest input, output and feature report of up to `REPORTS` report IDs
+pub struct Interface<'a, B, I, O, R, F = FeatureNone, const REPORTS: usize = 1>
 where
     B: UsbBus,
     I: InSize,
     O: OutSize,
     R: ReportCount,
+    F: FeatureSize,
 {
     id: InterfaceNumber,
-    config: InterfaceConfig<'a, I, O, R>,
+    config: InterfaceConfig<'a, I, O, R, F, REPORTS>,
     out_endpoint: Option<EndpointOut<'a, B>>,
     in_endpoint: EndpointIn<'a, B>,
     description_index: Option<StringIndex>,
     protocol: HidProtocol,
     report_idle: R::IdleStorage,
     global_idle: u8,
-    control_in_report_buffer: I::Buffer,
-    control_out_report_buffer: O::Buffer,
+    input_reports: ReportsById<I::Buffer, REPORTS>,
+    output_reports: ReportsById<O::Buffer, REPORTS>,
+    feature_reports: ReportsById<F::Buffer, REPORTS>,
 }
 
-impl<'a, B: UsbBus + 'a, I, O, R> UsbAllocatable<'a, B> for InterfaceConfig<'a, I, O, R>
+impl<'a, I, O, R, F, const REPORTS: usize> InterfaceConfig<'a, I, O, R, F, REPORTS>
+where
+    I: InSize,
+    O: OutSize,
+    R: ReportCount,
+    F: FeatureSize,
+{
+    /// The same interface with the larger `InSize` buffer `T`, the in endpoint keeps its max
+    /// packet size
+    pub(crate) fn with_in_size<T: InSize>(self) -> InterfaceConfig<'a, T, O, R, F, REPORTS> {
+        InterfaceConfig {
+            marker: PhantomData,
+            report_descriptor: self.report_descriptor,
+            report_descriptor_length: self.report_descriptor_length,
+            description: self.description,
+            protocol: self.protocol,
+            idle_default: self.idle_default,
+            out_endpoint: self.out_endpoint,
+            in_endpoint: EndpointConfig {
+                max_packet_size: Some(
+                    self.in_endpoint
+                        .max_packet_size
+                        .unwrap_or(I::Buffer::CAPACITY),
+                ),
+                ..self.in_endpoint
+            },
+            report_ids: self.report_ids,
+        }
+    }
+}
+
+impl<'a, B: UsbBus + 'a, I, O, R, F, const REPORTS: usize> UsbAllocatable<'a, B>
+    for InterfaceConfig<'a, I, O, R, F, REPORTS>
 where
     B: UsbBus,
     I: InSize,
     O: OutSize,
     R: ReportCount,
+    F: FeatureSize,
 {
-    type Allocated = Interface<'a, B, I, O, R>;
+    type Allocated = Interface<'a, B, I, O, R, F, REPORTS>;
 
     fn allocate(self, usb_alloc: &'a UsbBusAllocator<B>) -> Self::Allocated {
         Interface::new(usb_alloc, self)
     }
 }
 
-impl<'a, B, I, O, R> DeviceClass<'a> for Interface<'a, B, I, O, R>
+impl<'a, B, I, O, R, F, const REPORTS: usize> DeviceClass<'a>
+    for Interface<'a, B, I, O, R, F, REPORTS>
 where
     B: UsbBus,
     I: InSize,
     O: OutSize,
     R: ReportCount,
+    F: FeatureSize,
 {
     type I = Self;
 
@@ -317,17 +467,27 @@ where
     }
 }
 
-impl<'a, B: UsbBus, I, O, R> Interface<'a, B, I, O, R>
+impl<'a, B: UsbBus, I, O, R, F, const REPORTS: usize> Interface<'a, B, I, O, R, F, REPORTS>
 where
     B: UsbBus,
     I: InSize,
     O: OutSize,
     R: ReportCount,
+    F: FeatureSize,
 {
-    pub fn new(usb_alloc: &'a UsbBusAllocator<B>, config: InterfaceConfig<'a, I, O, R>) -> Self {
+    pub fn new(
+        usb_alloc: &'a UsbBusAllocator<B>,
+        config: InterfaceConfig<'a, I, O, R, F, REPORTS>,
+    ) -> Self {
         Interface {
             id: usb_alloc.interface(),
-            in_endpoint: usb_alloc.interrupt(I::Buffer::CAPACITY, config.in_endpoint.poll_interval),
+            in_endpoint: usb_alloc.interrupt(
+                config
+                    .in_endpoint
+                    .max_packet_size
+                    .unwrap_or(I::Buffer::CAPACITY),
+                config.in_endpoint.poll_interval,
+            ),
             out_endpoint: config
                 .out_endpoint
                 .map(|c| usb_alloc.interrupt(O::Buffer::CAPACITY, c.poll_interval)),
@@ -336,8 +496,9 @@ where
             protocol: HidProtocol::Report,
             report_idle: R::IdleStorage::default(),
             global_idle: config.idle_default,
-            control_in_report_buffer: I::Buffer::default(),
-            control_out_report_buffer: O::Buffer::default(),
+            input_reports: ReportsById::default(),
+            output_reports: ReportsById::default(),
+            feature_reports: ReportsById::default(),
             config,
         }
     }
@@ -369,25 +530,19 @@ where
                 .map(|i| (u32::from(i) * 4).millis())
         }
     }
+    /// Writes a report to the in endpoint, `GetReport` requests for input reports return the
+    /// latest report written with their report ID
     pub fn write_report(&mut self, data: &[u8]) -> usb_device::Result<usize> {
-        //Try to write report to the report buffer for the config endpoint
-        let control_result = if self.control_in_report_buffer.is_empty() {
-            match self.control_in_report_buffer.extend_from_slice(data) {
-                Ok(()) => Ok(data.len()),
-                Err(()) => Err(UsbError::BufferOverflow),
-            }
-        } else {
-            Err(UsbError::WouldBlock)
-        };
+        //Keep the latest report for GetReport requests on the control endpoint,
+        //even if the in endpoint is busy
+        let control_result = self.input_reports.write(self.report_id(data), data, false);
 
-        //Also try to write report to the in endpoint
         let endpoint_result = self.in_endpoint.write(data);
 
         match (control_result, endpoint_result) {
-            //OK if either succeeded
-            (_, Ok(n)) | (Ok(n), _) => Ok(n),
             //non-WouldBlock errors take preference
-            (Err(e), Err(UsbError::WouldBlock)) | (_, Err(e)) => Err(e),
+            (Err(e), Err(UsbError::WouldBlock)) => Err(e),
+            (_, endpoint_result) => endpoint_result,
         }
     }
     pub fn read_report(&mut self, data: &mut [u8]) -> usb_device::Result<usize> {
@@ -399,30 +554,69 @@ where
         };
 
         match ep_result {
-            Err(UsbError::WouldBlock) => {
-                //If there wasn't data available from the in endpoint
-                //try the config endpoint report buffer
-                let out_len = self.control_out_report_buffer.len();
-                if self.control_out_report_buffer.is_empty() {
-                    Err(UsbError::WouldBlock)
-                } else if data.len() < out_len {
-                    Err(UsbError::BufferOverflow)
-                } else {
-                    data[..out_len].copy_from_slice(self.control_out_report_buffer.as_ref());
-                    self.control_out_report_buffer.clear();
-                    Ok(out_len)
-                }
-            }
+            //If there wasn't data available from the out endpoint
+            //try the reports set on the control endpoint
+            Err(UsbError::WouldBlock) => Self::take_report(&mut self.output_reports, None, data),
             _ => ep_result,
         }
     }
+    /// Sets the feature report returned to the host for `report_id`, `data` starts with the
+    /// report ID if the report descriptor has Report ID items
+    pub fn write_feature_report(&mut self, report_id: u8, data: &[u8]) -> usb_device::Result<()> {
+        self.feature_reports.write(report_id, data, false)
+    }
+    /// Reads the feature report of `report_id` set by the host, `WouldBlock` until the host
+    /// sets it again
+    pub fn read_feature_report(
+        &mut self,
+        report_id: u8,
+        data: &mut [u8],
+    ) -> usb_device::Result<usize> {
+        Self::take_report(&mut self.feature_reports, Some(report_id), data)
+    }
+    fn report_id(&self, data: &[u8]) -> u8 {
+        if self.config.report_ids {
+            data.first().copied().unwrap_or_default()
+        } else {
+            0
+        }
+    }
+    fn take_report(
+        reports: &mut impl ReportStorage,
+        report_id: Option<u8>,
+        data: &mut [u8],
+    ) -> usb_device::Result<usize> {
+        let Some(report) = reports.take_changed(report_id) else {
+            return Err(UsbError::WouldBlock);
+        };
+        let out = data
+            .get_mut(..report.len())
+            .ok_or(UsbError::BufferOverflow)?;
+        out.copy_from_slice(report);
+        Ok(out.len())
+    }
+    fn copy_report(report: Option<&[u8]>, data: &mut [u8]) -> usb_device::Result<usize> {
+        let Some(report) = report else {
+            trace!("GetReport would block, no report");
+            return Err(UsbError::WouldBlock);
+        };
+        if data.len() < report.len() {
+            error!("GetReport failed, buffer too short");
+            Err(UsbError::BufferOverflow)
+        } else {
+            data[..report.len()].copy_from_slice(report);
+            Ok(report.len())
+        }
+    }
 }
-impl<'a, B: UsbBus, I, O, R> InterfaceClass<'a> for Interface<'a, B, I, O, R>
+impl<'a, B: UsbBus, I, O, R, F, const REPORTS: usize> InterfaceClass<'a>
+    for Interface<'a, B, I, O, R, F, REPORTS>
 where
     B: UsbBus,
     I: InSize,
     O: OutSize,
     R: ReportCount,
+    F: FeatureSize,
 {
     fn hid_descriptor_body(&self) -> [u8; 7] {
         match (HidDescriptorBody {
@@ -476,56 +670,52 @@ where
         self.protocol = HidProtocol::Report;
         self.global_idle = self.config.idle_default;
         self.clear_report_idle();
-        self.control_in_report_buffer = I::Buffer::default();
-        self.control_out_report_buffer = O::Buffer::default();
-    }
-    fn set_report(&mut self, data: &[u8]) -> usb_device::Result<()> {
-        if self.control_out_report_buffer.is_empty() {
-            if self
-                .control_out_report_buffer
-                .extend_from_slice(data)
-                .is_ok()
-            {
-                trace!(
-                    "Set report, {:X} bytes",
-                    &self.control_out_report_buffer.len()
-                );
-                Ok(())
-            } else {
-                error!(
-                    "Failed to set report, too large for buffer. Report size {:X}, expected <={:X}",
-                    data.len(),
-                    O::Buffer::CAPACITY
-                );
-                Err(UsbError::BufferOverflow)
-            }
-        } else {
-            trace!("Failed to set report, buffer not empty");
-            Err(UsbError::WouldBlock)
-        }
+        self.input_reports = ReportsById::default();
+        self.output_reports = ReportsById::default();
+        self.feature_reports = ReportsById::default();
     }
-
-    fn get_report(&self, data: &mut [u8]) -> usb_device::Result<usize> {
-        if self.control_in_report_buffer.is_empty() {
-            trace!("GetReport would block, empty buffer");
-            Err(UsbError::WouldBlock)
-        } else if data.len() < self.control_in_report_buffer.len() {
-            error!("GetReport failed, buffer too short");
-            Err(UsbError::BufferOverflow)
-        } else {
-            data[..self.control_in_report_buffer.len()]
-                .copy_from_slice(self.control_in_report_buffer.as_ref());
-            Ok(self.control_in_report_buffer.len())
+    fn set_report(
+        &mut self,
+        report_type: ReportType,
+        report_id: u8,
+        data: &[u8],
+    ) -> usb_device::Result<()> {
+        let result = match report_type {
+            ReportType::Output => self.output_reports.write(report_id, data, true),
+            ReportType::Feature => self.feature_reports.write(report_id, data, true),
+            ReportType::Input => {
+                warn!("SetReport is not supported for input reports");
+                return Err(UsbError::Unsupported);
+            }
+        };
+        match result {
+            Ok(()) => trace!(
+                "Set {:?} report ID{:X}, {:X} bytes",
+                report_type,
+                report_id,
+                data.len()
+            ),
+            Err(ref e) => error!(
+                "Failed to set {:?} report ID{:X} - {:?}",
+                report_type, report_id, e
+            ),
         }
+        result
     }
 
-    fn get_report_ack(&mut self) -> usb_device::Result<()> {
-        if self.control_in_report_buffer.is_empty() {
-            error!("GetReport ACK failed, empty buffer");
-            Err(UsbError::WouldBlock)
-        } else {
-            self.control_in_report_buffer.clear();
-            Ok(())
+    fn get_report(
+        &self,
+        report_type: ReportType,
+        report_id: u8,
+        data: &mut [u8],
+    ) -> usb_device::Result<usize> {
+        match report_type {
+            ReportType::Input => Self::copy_report(self.input_reports.read(report_id), data),
+            ReportType::Feature => Self::copy_report(self.feature_reports.read(report_id), data),
+            ReportType::Output => {
+                warn!("GetReport is not supported for output reports");
+                Err(UsbError::Unsupported)
+            }
         }
     }
 
@@ -572,24 +762,28 @@ where
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 struct EndpointConfig {
     pub poll_interval: u8,
+    /// Defaults to the report buffer capacity
+    pub max_packet_size: Option<u16>,
 }
 
 #[must_use = "this `UsbHidInterfaceBuilder` must be assigned or consumed by `::build_interface()`"]
 #[derive(Copy, Clone, Debug)]
-pub struct InterfaceBuilder<'a, I, O, R>
+pub struct InterfaceBuilder<'a, I, O, R, F = FeatureNone, const REPORTS: usize = 1>
 where
     I: InSize,
     O: OutSize,
     R: ReportCount,
+    F: FeatureSize,
 {
-    config: InterfaceConfig<'a, I, O, R>,
+    config: InterfaceConfig<'a, I, O, R, F, REPORTS>,
 }
 
-impl<'a, I, O, R> InterfaceBuilder<'a, I, O, R>
+impl<'a, I, O, R, F, const REPORTS: usize> InterfaceBuilder<'a, I, O, R, F, REPORTS>
 where
     I: InSize,
     O: OutSize,
     R: ReportCount,
+    F: FeatureSize,
 {
     pub fn new(report_descriptor: &'a [u8]) -> BuilderResult<Self> {
         if report_descriptor.len() > 128 {
@@ -606,7 +800,11 @@ where
                 protocol: InterfaceProtocol::None,
                 idle_default: 0,
                 out_endpoint: None,
-                in_endpoint: EndpointConfig { poll_interval: 20 },
+                in_endpoint: EndpointConfig {
+                    poll_interval: 20,
+                    max_packet_size: None,
+                },
+                report_ids: false,
             },
         })
     }
@@ -622,7 +820,11 @@ where
                 protocol: InterfaceProtocol::None,
                 idle_default: 0,
                 out_endpoint: None,
-                in_endpoint: EndpointConfig { poll_interval: 20 },
+                in_endpoint: EndpointConfig {
+                    poll_interval: 20,
+                    max_packet_size: None,
+                },
+                report_ids: false,
             },
         })
     }
@@ -658,6 +860,7 @@ where
         self.config.out_endpoint = Some(EndpointConfig {
             poll_interval: u8::try_from(poll_interval.to_millis())
                 .map_err(|_| UsbHidBuilderError::ValueOverflow)?,
+            max_packet_size: None,
         });
         Ok(self)
     }
@@ -668,16 +871,67 @@ where
     }
 
     pub fn in_endpoint(mut self, poll_interval: MillisDurationU32) -> BuilderResult<Self> {
-        self.config.in_endpoint = EndpointConfig {
-            poll_interval: u8::try_from(poll_interval.to_millis())
-                .map_err(|_| UsbHidBuilderError::ValueOverflow)?,
-        };
+        self.config.in_endpoint.poll_interval = u8::try_from(poll_interval.to_millis())
+            .map_err(|_| UsbHidBuilderError::ValueOverflow)?;
         Ok(self)
     }
 
-    #[must_use]
-    pub fn build(self) -> InterfaceConfig<'a, I, O, R> {
-        self.config
+    /// Shrinks the in endpoint max packet size below the `InSize` buffer capacity, for reports
+    /// shorter than the buffer
+    pub fn in_endpoint_size(mut self, max_packet_size: u16) -> BuilderResult<Self> {
+        if max_packet_size == 0 || max_packet_size > I::Buffer::CAPACITY {
+            return Err(UsbHidBuilderError::ValueOverflow);
+        }
+        self.config.in_endpoint.max_packet_size = Some(max_packet_size);
+        Ok(self)
+    }
+
+    /// Checks the report descriptor is well formed and its input, output and feature reports
+    /// fit the `InSize`, `OutSize` and `FeatureSize` buffers
+    pub fn build(mut self) -> BuilderResult<InterfaceConfig<'a, I, O, R, F, REPORTS>> {
+        let (ReportDescriptor::StaticDescriptor(descriptor)
+        | ReportDescriptor::DynamicDescriptor(descriptor)) = self.config.report_descriptor;
+        let parsed = ParsedReportDescriptor::parse(descriptor)
+            .map_err(UsbHidBuilderError::InvalidReportDescriptor)?;
+
+        let in_len = self
+            .config
+            .in_endpoint
+            .max_packet_size
+            .unwrap_or(I::Buffer::CAPACITY);
+        if parsed.max_input_len() > usize::from(in_len)
+            || parsed.max_output_len() > usize::from(O::Buffer::CAPACITY)
+            || parsed.max_feature_len() > usize::from(F::Buffer::CAPACITY)
+        {
+            error!(
+                "Report too long for interface buffers, input {}, output {}, feature {}",
+                parsed.max_input_len(),
+                parsed.max_output_len(),
+                parsed.max_feature_len()
+            );
+            return Err(UsbHidBuilderError::ReportLengthOverflow);
+        }
+
+        let report_ids = |bits: fn(&ReportSizes) -> u32| {
+            parsed
+                .reports()
+                .iter()
+                .filter(|report| bits(report) > 0)
+                .count()
+        };
+        if report_ids(|report| report.input_bits) > REPORTS
+            || report_ids(|report| report.output_bits) > REPORTS
+            || report_ids(|report| report.feature_bits) > REPORTS
+        {
+            error!(
+                "Too many report IDs for the interface, expected <={}",
+                REPORTS
+            );
+            return Err(UsbHidBuilderError::TooManyReportIds);
+        }
+
+        self.config.report_ids = parsed.reports().iter().any(|report| report.report_id != 0);
+        Ok(self.config)
     }
 }
 
@@ -145,6 +145,9 @@ pub(crate) mod fmt;
 #[cfg(test)]
 extern crate std;
 
+//Allow code generated by the derive macro to refer to this crate by name
+extern crate self as usbd_human_interface_device;
+
 use usb_device::UsbError;
 
 pub mod descriptor;
@@ -152,6 +155,7 @@ pub mod device;
 pub mod interface;
 pub mod page;
 pub mod prelude;
+pub mod report_descriptor;
 pub mod usb_class;
 
 #[derive(Debug)]
@@ -0,0 +1,326 @@
+//! Typed report descriptor builder
+//!
+//! Every method is a `const fn`, so descriptors can be built into constants, failing
+//! compilation when they are malformed:
+//!
+//! ```
+//! use usbd_human_interface_device::page::Desktop;
+//! use usbd_human_interface_device::report_descriptor::builder::{
+//!     ReportDescriptorBuf, ReportDescriptorBuilder,
+//! };
+//! use usbd_human_interface_device::report_descriptor::{Collection, MainFlags, Usage, UsagePage};
+//!
+//! const DIAL: ReportDescriptorBuf<32> = ReportDescriptorBuilder::new()
+//!     .usage_page(UsagePage::Desktop)
+//!     .usage(Usage::Desktop(Desktop::Joystick))
+//!     .collection(Collection::Application)
+//!     .usage(Usage::Desktop(Desktop::Dial))
+//!     .logical_minimum(0)
+//!     .logical_maximum(255)
+//!     .report_size(8)
+//!     .report_count(1)
+//!     .input(MainFlags::VARIABLE)
+//!     .end_collection()
+//!     .finish();
+//!
+//! const DESCRIPTOR: &[u8] = DIAL.as_bytes();
+//! ```
+
+use crate::report_descriptor::{
+    tag, Collection, MainFlags, ReportDescriptorError, Usage, UsagePage,
+};
+
+/// Builds a report descriptor of at most `N` bytes, item by item
+///
+/// Usages of the current usage page are written as short usages, usages of other pages as
+/// extended usages, so they are always read on their own page.
+#[must_use = "this `ReportDescriptorBuilder` must be consumed by `::build()` or `::finish()`"]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct ReportDescriptorBuilder<const N: usize> {
+    bytes: [u8; N],
+    len: usize,
+    /// Collections opened and not yet ended
+    depth: usize,
+    usage_page: Option<u16>,
+    error: Option<ReportDescriptorError>,
+}
+
+impl<const N: usize> Default for ReportDescriptorBuilder<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> ReportDescriptorBuilder<N> {
+    pub const fn new() -> Self {
+        Self {
+            bytes: [0; N],
+            len: 0,
+            depth: 0,
+            usage_page: None,
+            error: None,
+        }
+    }
+
+    const fn byte(mut self, byte: u8) -> Self {
+        if self.len < N {
+            self.bytes[self.len] = byte;
+            self.len += 1;
+        } else {
+            self.error = Some(ReportDescriptorError::BufferOverflow);
+        }
+        self
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    const fn data(self, prefix: u8, data: [u8; 4], len: usize) -> Self {
+        let size = if len == 4 { 3 } else { len as u8 };
+        let mut builder = self.byte(prefix | size);
+        let mut i = 0;
+        while i < len {
+            builder = builder.byte(data[i]);
+            i += 1;
+        }
+        builder
+    }
+
+    /// Item with the shortest unsigned encoding of `value`
+    const fn unsigned(self, prefix: u8, value: u32) -> Self {
+        let len = match value {
+            0..=0xff => 1,
+            0x100..=0xffff => 2,
+            _ => 4,
+        };
+        self.data(prefix, value.to_le_bytes(), len)
+    }
+
+    /// Item with the shortest signed encoding of `value`
+    const fn signed(self, prefix: u8, value: i32) -> Self {
+        let len = match value {
+            -0x80..=0x7f => 1,
+            -0x8000..=0x7fff => 2,
+            _ => 4,
+        };
+        self.data(prefix, value.to_le_bytes(), len)
+    }
+
+    const fn local_usage(self, prefix: u8, usage: Usage) -> Self {
+        let page = usage.page() as u16;
+        match self.usage_page {
+            Some(current) if current == page => self.unsigned(prefix, usage.id() as u32),
+            _ => self.data(
+                prefix,
+                ((page as u32) << 16 | usage.id() as u32).to_le_bytes(),
+                4,
+            ),
+        }
+    }
+
+    pub const fn usage_page(mut self, page: UsagePage) -> Self {
+        self.usage_page = Some(page as u16);
+        self.unsigned(tag::USAGE_PAGE, page as u32)
+    }
+
+    pub const fn usage(self, usage: Usage) -> Self {
+        self.local_usage(tag::USAGE, usage)
+    }
+
+    pub const fn usage_minimum(self, usage: Usage) -> Self {
+        self.local_usage(tag::USAGE_MINIMUM, usage)
+    }
+
+    pub const fn usage_maximum(self, usage: Usage) -> Self {
+        self.local_usage(tag::USAGE_MAXIMUM, usage)
+    }
+
+    pub const fn collection(mut self, collection: Collection) -> Self {
+        self.depth += 1;
+        self.unsigned(tag::COLLECTION, collection as u32)
+    }
+
+    pub const fn end_collection(mut self) -> Self {
+        if self.depth == 0 {
+            self.error = Some(ReportDescriptorError::UnbalancedCollection);
+            return self;
+        }
+        self.depth -= 1;
+        self.byte(tag::END_COLLECTION)
+    }
+
+    pub const fn logical_minimum(self, value: i32) -> Self {
+        self.signed(tag::LOGICAL_MINIMUM, value)
+    }
+
+    pub const fn logical_maximum(self, value: i32) -> Self {
+        self.signed(tag::LOGICAL_MAXIMUM, value)
+    }
+
+    pub const fn physical_minimum(self, value: i32) -> Self {
+        self.signed(tag::PHYSICAL_MINIMUM, value)
+    }
+
+    pub const fn physical_maximum(self, value: i32) -> Self {
+        self.signed(tag::PHYSICAL_MAXIMUM, value)
+    }
+
+    /// Unit system and exponents, `0` for none
+    pub const fn unit(self, unit: u32) -> Self {
+        self.unsigned(tag::UNIT, unit)
+    }
+
+    pub const fn unit_exponent(self, exponent: i8) -> Self {
+        self.signed(tag::UNIT_EXPONENT, exponent as i32)
+    }
+
+    /// Bits of every field
+    pub const fn report_size(self, bits: u32) -> Self {
+        self.unsigned(tag::REPORT_SIZE, bits)
+    }
+
+    pub const fn report_count(self, count: u32) -> Self {
+        self.unsigned(tag::REPORT_COUNT, count)
+    }
+
+    pub const fn report_id(self, id: u8) -> Self {
+        self.unsigned(tag::REPORT_ID, id as u32)
+    }
+
+    pub const fn input(self, flags: MainFlags) -> Self {
+        self.unsigned(tag::INPUT, flags.0 as u32)
+    }
+
+    pub const fn output(self, flags: MainFlags) -> Self {
+        self.unsigned(tag::OUTPUT, flags.0 as u32)
+    }
+
+    pub const fn feature(self, flags: MainFlags) -> Self {
+        self.unsigned(tag::FEATURE, flags.0 as u32)
+    }
+
+    /// Constant field of `bits`, e.g. to pad a report to a whole byte
+    pub const fn padding(self, bits: u32) -> Self {
+        self.report_size(bits)
+            .report_count(1)
+            .input(MainFlags::CONSTANT.union(MainFlags::VARIABLE))
+    }
+
+    pub const fn build(self) -> Result<ReportDescriptorBuf<N>, ReportDescriptorError> {
+        if let Some(error) = self.error {
+            Err(error)
+        } else if self.depth != 0 {
+            Err(ReportDescriptorError::UnbalancedCollection)
+        } else {
+            Ok(ReportDescriptorBuf {
+                bytes: self.bytes,
+                len: self.len,
+            })
+        }
+    }
+
+    /// Like [`ReportDescriptorBuilder::build`], panics on malformed descriptors, which fails
+    /// compilation in a `const`
+    #[must_use]
+    pub const fn finish(self) -> ReportDescriptorBuf<N> {
+        // `core::panic!`, the crate's `panic!` logs with defmt, which can't run in a `const`
+        match self.build() {
+            Ok(descriptor) => descriptor,
+            Err(ReportDescriptorError::BufferOverflow) => {
+                core::panic!("report descriptor does not fit the buffer")
+            }
+            Err(ReportDescriptorError::UnbalancedCollection) => {
+                core::panic!("report descriptor collections are unbalanced")
+            }
+            Err(_) => core::panic!("report descriptor is malformed"),
+        }
+    }
+}
+
+/// Report descriptor built by [`ReportDescriptorBuilder`]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct ReportDescriptorBuf<const N: usize> {
+    bytes: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> ReportDescriptorBuf<N> {
+    #[must_use]
+    pub const fn as_bytes(&self) -> &[u8] {
+        self.bytes.split_at(self.len).0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::device::joystick::DEFAULT_JOYSTICK_DESCRIPTOR;
+    use crate::page::{Consumer, Desktop};
+    use crate::report_descriptor::builder::ReportDescriptorBuilder;
+    use crate::report_descriptor::{
+        Collection, MainFlags, ReportDescriptorError, Usage, UsagePage,
+    };
+
+    #[test]
+    fn default_joystick_descriptor() {
+        let descriptor = ReportDescriptorBuilder::<64>::new()
+            .usage_page(UsagePage::Desktop)
+            .usage(Usage::Desktop(Desktop::Joystick))
+            .collection(Collection::Application)
+            .usage(Usage::Desktop(Desktop::Pointer))
+            .collection(Collection::Physical)
+            .usage(Usage::Desktop(Desktop::X))
+            .usage(Usage::Desktop(Desktop::Y))
+            .usage(Usage::Desktop(Desktop::Z))
+            .logical_minimum(0)
+            .logical_maximum(1024)
+            .report_size(16)
+            .report_count(3)
+            .input(MainFlags::VARIABLE)
+            .end_collection()
+            .usage_page(UsagePage::Button)
+            .usage_minimum(Usage::Button(1))
+            .usage_maximum(Usage::Button(8))
+            .logical_minimum(0)
+            .logical_maximum(1)
+            .report_size(1)
+            .report_count(8)
+            .input(MainFlags::VARIABLE)
+            .end_collection()
+            .finish();
+
+        assert_eq!(descriptor.as_bytes(), DEFAULT_JOYSTICK_DESCRIPTOR);
+    }
+
+    #[test]
+    fn usage_of_other_page_is_extended() {
+        let descriptor = ReportDescriptorBuilder::<16>::new()
+            .usage_page(UsagePage::Desktop)
+            .usage(Usage::Consumer(Consumer::VolumeIncrement))
+            .finish();
+
+        assert_eq!(
+            descriptor.as_bytes(),
+            &[0x05, 0x01, 0x0b, 0xe9, 0x00, 0x0c, 0x00]
+        );
+    }
+
+    #[test]
+    fn unbalanced_collections() {
+        let unended = ReportDescriptorBuilder::<16>::new()
+            .collection(Collection::Application)
+            .build();
+        let unopened = ReportDescriptorBuilder::<16>::new()
+            .end_collection()
+            .build();
+
+        assert_eq!(unended, Err(ReportDescriptorError::UnbalancedCollection));
+        assert_eq!(unopened, Err(ReportDescriptorError::UnbalancedCollection));
+    }
+
+    #[test]
+    fn buffer_overflow() {
+        let descriptor = ReportDescriptorBuilder::<2>::new()
+            .logical_maximum(1024)
+            .build();
+
+        assert_eq!(descriptor, Err(ReportDescriptorError::BufferOverflow));
+    }
+}
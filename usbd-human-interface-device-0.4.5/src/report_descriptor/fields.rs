@@ -0,0 +1,171 @@
+//! Report field packing, used by reports derived with [`HidReport`](super::HidReport)
+//!
+//! Fields are packed least significant bit first, in descriptor order, as read by the host.
+
+use crate::private::Sealed;
+use crate::report_descriptor::builder::ReportDescriptorBuf;
+pub use packed_struct::{PackedStruct, PackingError, PackingResult};
+
+/// Field types of derived reports
+pub trait ReportField: Sealed + Copy {
+    /// Bits of the value, only the low bits of the report field are written
+    fn to_bits(self) -> u64;
+    /// Value of the low `len` bits, sign extended for signed types
+    fn from_bits(bits: u64, len: usize) -> Self;
+}
+
+impl Sealed for bool {}
+impl ReportField for bool {
+    fn to_bits(self) -> u64 {
+        u64::from(self)
+    }
+
+    fn from_bits(bits: u64, _len: usize) -> Self {
+        bits != 0
+    }
+}
+
+macro_rules! unsigned_report_field {
+    ($($ty: ty),*) => {
+        $(
+        impl Sealed for $ty {}
+        impl ReportField for $ty {
+            fn to_bits(self) -> u64 {
+                u64::from(self)
+            }
+
+            #[allow(clippy::cast_possible_truncation)]
+            fn from_bits(bits: u64, _len: usize) -> Self {
+                bits as Self
+            }
+        }
+        )*
+    };
+}
+
+macro_rules! signed_report_field {
+    ($($ty: ty),*) => {
+        $(
+        impl Sealed for $ty {}
+        impl ReportField for $ty {
+            #[allow(clippy::cast_sign_loss)]
+            fn to_bits(self) -> u64 {
+                i64::from(self) as u64
+            }
+
+            #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+            fn from_bits(bits: u64, len: usize) -> Self {
+                let unused = 64 - len;
+                ((bits << unused) as i64 >> unused) as Self
+            }
+        }
+        )*
+    };
+}
+
+unsigned_report_field!(u8, u16, u32);
+signed_report_field!(i8, i16, i32);
+
+/// Sets the low `bits` of `value` into `report`, starting at bit `offset`
+pub fn write_bits(report: &mut [u8], offset: usize, bits: usize, value: u64) {
+    for bit in (0..bits).filter(|bit| value & 1 << bit != 0) {
+        report[(offset + bit) / 8] |= 1 << ((offset + bit) % 8);
+    }
+}
+
+/// Reads `bits` of `report`, starting at bit `offset`
+#[must_use]
+pub fn read_bits(report: &[u8], offset: usize, bits: usize) -> u64 {
+    (0..bits)
+        .filter(|bit| report[(offset + bit) / 8] & 1 << ((offset + bit) % 8) != 0)
+        .fold(0, |value, bit| value | 1 << bit)
+}
+
+/// Copies a built descriptor into an array of its exact length, so no unused buffer is kept
+#[must_use]
+pub const fn descriptor_bytes<const N: usize, const LEN: usize>(
+    buffer: &ReportDescriptorBuf<N>,
+) -> [u8; LEN] {
+    let bytes = buffer.as_bytes();
+    let mut exact = [0; LEN];
+    let mut i = 0;
+    while i < LEN {
+        exact[i] = bytes[i];
+        i += 1;
+    }
+    exact
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use crate::report_descriptor::fields::PackedStruct;
+    use crate::report_descriptor::HidReport;
+
+    #[derive(Clone, Copy, Debug, Default, Eq, PartialEq, HidReport)]
+    #[hid(usage = Desktop::MultiAxisController, report_id = 2)]
+    struct SignedReport {
+        #[hid(usage = Desktop::Rx, logical_min = -100, logical_max = 100)]
+        rx: i8,
+        #[hid(usage = Desktop::Wheel, size = 12, padding = 4)]
+        wheel: i16,
+        #[hid(usage_min = Button(1), usage_max = Button(3), size = 1, padding = 5)]
+        buttons: [bool; 3],
+    }
+
+    #[test]
+    fn signed_report_descriptor() {
+        #[rustfmt::skip]
+        let expected: &[u8] = &[
+            0x05, 0x01,       // Usage Page (Generic Desktop)
+            0x09, 0x08,       // Usage (Multi-axis Controller)
+            0xa1, 0x01,       // Collection (Application)
+            0x85, 0x02,       //   Report ID (2)
+            0x09, 0x33,       //   Usage (Rx)
+            0x15, 0x9c,       //   Logical Minimum (-100)
+            0x25, 0x64,       //   Logical Maximum (100)
+            0x75, 0x08,       //   Report Size (8)
+            0x95, 0x01,       //   Report Count (1)
+            0x81, 0x02,       //   Input (Data, Variable, Absolute)
+            0x09, 0x38,       //   Usage (Wheel)
+            0x16, 0x00, 0xf8, //   Logical Minimum (-2048)
+            0x26, 0xff, 0x07, //   Logical Maximum (2047)
+            0x75, 0x0c,       //   Report Size (12)
+            0x95, 0x01,       //   Report Count (1)
+            0x81, 0x02,       //   Input (Data, Variable, Absolute)
+            0x75, 0x04,       //   Report Size (4)
+            0x95, 0x01,       //   Report Count (1)
+            0x81, 0x03,       //   Input (Constant, Variable, Absolute)
+            0x05, 0x09,       //   Usage Page (Button)
+            0x19, 0x01,       //   Usage Minimum (1)
+            0x29, 0x03,       //   Usage Maximum (3)
+            0x15, 0x00,       //   Logical Minimum (0)
+            0x25, 0x01,       //   Logical Maximum (1)
+            0x75, 0x01,       //   Report Size (1)
+            0x95, 0x03,       //   Report Count (3)
+            0x81, 0x02,       //   Input (Data, Variable, Absolute)
+            0x75, 0x05,       //   Report Size (5)
+            0x95, 0x01,       //   Report Count (1)
+            0x81, 0x03,       //   Input (Constant, Variable, Absolute)
+            0xc0,             // End Collection
+        ];
+
+        assert_eq!(SignedReport::DESCRIPTOR, expected);
+    }
+
+    #[test]
+    fn signed_report_pack() {
+        let report = SignedReport {
+            rx: -2,
+            wheel: -3,
+            buttons: [true, false, true],
+        };
+
+        let packed = report.pack().unwrap();
+
+        assert_eq!(packed, [0x02, 0xfe, 0xfd, 0x0f, 0b101]);
+        assert_eq!(SignedReport::unpack(&packed).unwrap(), report);
+        assert!(SignedReport::unpack(&[0x01, 0, 0, 0, 0]).is_err());
+    }
+}
@@ -0,0 +1,203 @@
+//! HID report descriptors
+//!
+//! See Device Class Definition for Human Interface Devices (HID) Version 1.11, section 6.2.2
+//! <https://www.usb.org/sites/default/files/hid1_11.pdf>
+
+use crate::page::{Consumer, Desktop, Game, Keyboard, Leds, Simulation, Telephony};
+use packed_struct::PackedStruct;
+
+pub mod builder;
+#[doc(hidden)]
+pub mod fields;
+pub mod parser;
+
+pub use usbd_human_interface_device_derive::HidReport;
+
+/// Short item prefixes, tag and type with the size bits cleared
+pub(crate) mod tag {
+    pub const INPUT: u8 = 0x80;
+    pub const OUTPUT: u8 = 0x90;
+    pub const FEATURE: u8 = 0xb0;
+    pub const COLLECTION: u8 = 0xa0;
+    pub const END_COLLECTION: u8 = 0xc0;
+    pub const USAGE_PAGE: u8 = 0x04;
+    pub const LOGICAL_MINIMUM: u8 = 0x14;
+    pub const LOGICAL_MAXIMUM: u8 = 0x24;
+    pub const PHYSICAL_MINIMUM: u8 = 0x34;
+    pub const PHYSICAL_MAXIMUM: u8 = 0x44;
+    pub const UNIT_EXPONENT: u8 = 0x54;
+    pub const UNIT: u8 = 0x64;
+    pub const REPORT_SIZE: u8 = 0x74;
+    pub const REPORT_ID: u8 = 0x84;
+    pub const REPORT_COUNT: u8 = 0x94;
+    pub const PUSH: u8 = 0xa4;
+    pub const POP: u8 = 0xb4;
+    pub const USAGE: u8 = 0x08;
+    pub const USAGE_MINIMUM: u8 = 0x18;
+    pub const USAGE_MAXIMUM: u8 = 0x28;
+}
+
+/// Errors of a malformed report descriptor
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ReportDescriptorError {
+    /// The descriptor does not fit its buffer
+    BufferOverflow,
+    /// An End Collection without a Collection, or a Collection which is not ended
+    UnbalancedCollection,
+    /// A Pop without a Push, or Push items nested too deeply
+    UnbalancedPush,
+    /// An item runs past the end of the descriptor
+    Truncated,
+    /// A Report ID of `0` or above 255
+    InvalidReportId,
+    /// More report IDs than [`parser::MAX_REPORT_IDS`]
+    TooManyReports,
+}
+
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[repr(u8)]
+pub enum Collection {
+    Physical = 0x00,
+    Application = 0x01,
+    Logical = 0x02,
+    Report = 0x03,
+    NamedArray = 0x04,
+    UsageSwitch = 0x05,
+    UsageModifier = 0x06,
+}
+
+/// Usage pages with enums in [`crate::page`], plus the Button and Ordinal pages
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[repr(u16)]
+pub enum UsagePage {
+    Desktop = 0x01,
+    Simulation = 0x02,
+    Game = 0x05,
+    Keyboard = 0x07,
+    Leds = 0x08,
+    Button = 0x09,
+    Ordinal = 0x0a,
+    Telephony = 0x0b,
+    Consumer = 0x0c,
+}
+
+/// Usage together with its usage page
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Usage {
+    Desktop(Desktop),
+    Simulation(Simulation),
+    Game(Game),
+    Keyboard(Keyboard),
+    Leds(Leds),
+    /// Button number, `0` is no button pressed
+    Button(u16),
+    /// Instance number
+    Ordinal(u16),
+    Telephony(Telephony),
+    Consumer(Consumer),
+}
+
+impl Usage {
+    #[must_use]
+    pub const fn page(self) -> UsagePage {
+        match self {
+            Self::Desktop(_) => UsagePage::Desktop,
+            Self::Simulation(_) => UsagePage::Simulation,
+            Self::Game(_) => UsagePage::Game,
+            Self::Keyboard(_) => UsagePage::Keyboard,
+            Self::Leds(_) => UsagePage::Leds,
+            Self::Button(_) => UsagePage::Button,
+            Self::Ordinal(_) => UsagePage::Ordinal,
+            Self::Telephony(_) => UsagePage::Telephony,
+            Self::Consumer(_) => UsagePage::Consumer,
+        }
+    }
+
+    /// Usage ID within the page
+    #[must_use]
+    pub const fn id(self) -> u16 {
+        match self {
+            Self::Desktop(usage) => usage as u16,
+            Self::Simulation(usage) => usage as u16,
+            Self::Game(usage) => usage as u16,
+            Self::Keyboard(usage) => usage as u16,
+            Self::Leds(usage) => usage as u16,
+            Self::Button(id) | Self::Ordinal(id) => id,
+            Self::Telephony(usage) => usage as u16,
+            Self::Consumer(usage) => usage as u16,
+        }
+    }
+}
+
+/// Data flags of Input, Output and Feature items, the zero flags are the defaults:
+/// data, array, absolute
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct MainFlags(pub u16);
+
+impl MainFlags {
+    pub const DATA: Self = Self(0x00);
+    pub const CONSTANT: Self = Self(0x01);
+    pub const VARIABLE: Self = Self(0x02);
+    pub const RELATIVE: Self = Self(0x04);
+    pub const WRAP: Self = Self(0x08);
+    pub const NON_LINEAR: Self = Self(0x10);
+    pub const NO_PREFERRED: Self = Self(0x20);
+    pub const NULL_STATE: Self = Self(0x40);
+    pub const VOLATILE: Self = Self(0x80);
+    pub const BUFFERED_BYTES: Self = Self(0x100);
+
+    #[must_use]
+    pub const fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+/// Report struct with a matching report descriptor
+///
+/// Derived with `#[derive(HidReport)]`, which generates both the [`PackedStruct`] implementation
+/// and [`HidReport::DESCRIPTOR`] from `#[hid(...)]` attributes, so the two can not drift apart.
+/// Fields are packed least significant bit first in declaration order, and the report has to
+/// be a whole number of bytes.
+///
+/// The struct attribute takes:
+/// - `usage`: usage of the application collection, e.g. `Desktop::Joystick`
+/// - `report_id`: report ID, reported as the first byte
+/// - `output` or `feature`: describe Output or Feature items instead of Input items
+///
+/// Every field is a `bool`, `u8`, `u16`, `u32`, `i8`, `i16`, `i32` or an array of them, and
+/// takes:
+/// - `usage`, or `usage_min` and `usage_max`: usages such as `Desktop::X` or `Button(1)`
+/// - `logical_min` and `logical_max`: defaults to the range of `size` bits of the field type
+/// - `size`: bits of every report field, defaults to the field type width
+/// - `count`: report fields packed in a scalar field, e.g. eight one bit buttons in a `u8`.
+///   Arrays have one report field per element
+/// - `collection`: collection of the field, e.g. `Physical(Desktop::Pointer)`, consecutive
+///   fields with the same collection share it
+/// - `relative` and `null_state`: flags of the main item
+/// - `padding`: constant bits after the field
+///
+/// Consecutive fields with a single usage and identical items share a main item.
+///
+/// ```
+/// use usbd_human_interface_device::report_descriptor::HidReport;
+///
+/// #[derive(Clone, Copy, Debug, Default, Eq, PartialEq, HidReport)]
+/// #[hid(usage = Desktop::Joystick)]
+/// pub struct ThrottleReport {
+///     #[hid(usage = Simulation::Throttle, logical_max = 1023, size = 10, padding = 6)]
+///     pub throttle: u16,
+///     #[hid(usage_min = Button(1), usage_max = Button(8), count = 8)]
+///     pub buttons: u8,
+/// }
+///
+/// assert_eq!(ThrottleReport::DESCRIPTOR.len(), 44);
+/// ```
+pub trait HidReport: PackedStruct {
+    /// Report descriptor of the packed report
+    const DESCRIPTOR: &'static [u8];
+}
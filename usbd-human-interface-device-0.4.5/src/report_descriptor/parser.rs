@@ -0,0 +1,258 @@
+//! Report descriptor parser
+//!
+//! Walks the items of a report descriptor and sums the Input, Output and Feature fields of
+//! every report ID, so the lengths of the reports can be checked against the interface
+//! buffers.
+
+use crate::report_descriptor::{tag, ReportDescriptorError};
+use heapless::Vec;
+
+/// Report IDs of a parsed descriptor at most
+pub const MAX_REPORT_IDS: usize = 16;
+/// Nesting of Push items at most
+const MAX_PUSH_DEPTH: usize = 4;
+/// Prefix of long items, which carry their own data length
+const LONG_ITEM: u8 = 0xfe;
+
+/// Bits of the reports with one report ID
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct ReportSizes {
+    /// `0` for descriptors without Report ID items
+    pub report_id: u8,
+    pub input_bits: u32,
+    pub output_bits: u32,
+    pub feature_bits: u32,
+}
+
+impl ReportSizes {
+    /// Bytes of a report of `bits`, including the report ID prefix
+    fn len(&self, bits: u32) -> usize {
+        if bits == 0 {
+            0
+        } else {
+            bits.div_ceil(8) as usize + usize::from(self.report_id != 0)
+        }
+    }
+
+    #[must_use]
+    pub fn input_len(&self) -> usize {
+        self.len(self.input_bits)
+    }
+
+    #[must_use]
+    pub fn output_len(&self) -> usize {
+        self.len(self.output_bits)
+    }
+
+    #[must_use]
+    pub fn feature_len(&self) -> usize {
+        self.len(self.feature_bits)
+    }
+}
+
+/// Global items sizing the fields
+#[derive(Clone, Copy, Default)]
+struct Globals {
+    size: u32,
+    count: u32,
+    id: u8,
+}
+
+/// Report sizes of a well formed report descriptor
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct ParsedReportDescriptor {
+    reports: Vec<ReportSizes, MAX_REPORT_IDS>,
+}
+
+impl ParsedReportDescriptor {
+    pub fn parse(descriptor: &[u8]) -> Result<Self, ReportDescriptorError> {
+        let mut parsed = Self::default();
+        let mut globals = Globals::default();
+        let mut pushed: Vec<Globals, MAX_PUSH_DEPTH> = Vec::new();
+        let mut depth = 0_usize;
+        let mut rest = descriptor;
+
+        while let Some((&prefix, tail)) = rest.split_first() {
+            if prefix == LONG_ITEM {
+                let len = tail
+                    .first()
+                    .map(|&size| 2 + usize::from(size))
+                    .ok_or(ReportDescriptorError::Truncated)?;
+                rest = tail.get(len..).ok_or(ReportDescriptorError::Truncated)?;
+                continue;
+            }
+
+            let size = match prefix & 0x03 {
+                3 => 4,
+                size => usize::from(size),
+            };
+            let data = tail.get(..size).ok_or(ReportDescriptorError::Truncated)?;
+            rest = &tail[size..];
+            let value = data
+                .iter()
+                .rev()
+                .fold(0_u32, |value, &byte| value << 8 | u32::from(byte));
+
+            match prefix & !0x03 {
+                tag::REPORT_SIZE => globals.size = value,
+                tag::REPORT_COUNT => globals.count = value,
+                tag::REPORT_ID => {
+                    globals.id = u8::try_from(value)
+                        .ok()
+                        .filter(|&id| id != 0)
+                        .ok_or(ReportDescriptorError::InvalidReportId)?;
+                }
+                tag::PUSH => pushed
+                    .push(globals)
+                    .map_err(|_| ReportDescriptorError::UnbalancedPush)?,
+                tag::POP => globals = pushed.pop().ok_or(ReportDescriptorError::UnbalancedPush)?,
+                tag::COLLECTION => depth += 1,
+                tag::END_COLLECTION => {
+                    depth = depth
+                        .checked_sub(1)
+                        .ok_or(ReportDescriptorError::UnbalancedCollection)?;
+                }
+                main @ (tag::INPUT | tag::OUTPUT | tag::FEATURE) => {
+                    let bits = globals.size.saturating_mul(globals.count);
+                    let report = parsed.report_mut(globals.id)?;
+                    let field = match main {
+                        tag::INPUT => &mut report.input_bits,
+                        tag::OUTPUT => &mut report.output_bits,
+                        _ => &mut report.feature_bits,
+                    };
+                    *field = field.saturating_add(bits);
+                }
+                _ => {}
+            }
+        }
+
+        if depth == 0 {
+            Ok(parsed)
+        } else {
+            Err(ReportDescriptorError::UnbalancedCollection)
+        }
+    }
+
+    fn report_mut(&mut self, report_id: u8) -> Result<&mut ReportSizes, ReportDescriptorError> {
+        let position = self
+            .reports
+            .iter()
+            .position(|report| report.report_id == report_id);
+        let index = if let Some(index) = position {
+            index
+        } else {
+            self.reports
+                .push(ReportSizes {
+                    report_id,
+                    ..ReportSizes::default()
+                })
+                .map_err(|_| ReportDescriptorError::TooManyReports)?;
+            self.reports.len() - 1
+        };
+        Ok(&mut self.reports[index])
+    }
+
+    /// Reports in the order their IDs first appear
+    #[must_use]
+    pub fn reports(&self) -> &[ReportSizes] {
+        &self.reports
+    }
+
+    #[must_use]
+    pub fn report(&self, report_id: u8) -> Option<&ReportSizes> {
+        self.reports
+            .iter()
+            .find(|report| report.report_id == report_id)
+    }
+
+    /// Bytes of the longest input report, including the report ID prefix
+    #[must_use]
+    pub fn max_input_len(&self) -> usize {
+        self.reports
+            .iter()
+            .map(ReportSizes::input_len)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Bytes of the longest output report, including the report ID prefix
+    #[must_use]
+    pub fn max_output_len(&self) -> usize {
+        self.reports
+            .iter()
+            .map(ReportSizes::output_len)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Bytes of the longest feature report, including the report ID prefix
+    #[must_use]
+    pub fn max_feature_len(&self) -> usize {
+        self.reports
+            .iter()
+            .map(ReportSizes::feature_len)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use crate::device::joystick::DEFAULT_JOYSTICK_DESCRIPTOR;
+    use crate::report_descriptor::parser::{ParsedReportDescriptor, ReportSizes};
+    use crate::report_descriptor::ReportDescriptorError;
+
+    #[test]
+    fn default_joystick_sizes() {
+        let parsed = ParsedReportDescriptor::parse(DEFAULT_JOYSTICK_DESCRIPTOR);
+
+        assert_eq!(
+            parsed.as_ref().map(ParsedReportDescriptor::reports),
+            Ok(&[ReportSizes {
+                report_id: 0,
+                input_bits: 56,
+                output_bits: 0,
+                feature_bits: 0,
+            }][..])
+        );
+    }
+
+    #[test]
+    fn sizes_per_report_id() {
+        #[rustfmt::skip]
+        let descriptor = [
+            0x85, 0x01, 0x75, 0x08, 0x95, 0x03, 0x81, 0x02, // ID 1, 3 input bytes
+            0x95, 0x02, 0xb1, 0x02,                         // 2 feature bytes
+            0x85, 0x02, 0x75, 0x01, 0x95, 0x05, 0x91, 0x02, // ID 2, 5 output bits
+        ];
+        let parsed = ParsedReportDescriptor::parse(&descriptor).unwrap();
+
+        assert_eq!(parsed.report(1).map(ReportSizes::input_len), Some(4));
+        assert_eq!(parsed.report(1).map(ReportSizes::feature_len), Some(3));
+        assert_eq!(parsed.report(2).map(ReportSizes::output_len), Some(2));
+        assert_eq!(parsed.max_input_len(), 4);
+    }
+
+    #[test]
+    fn malformed_descriptors() {
+        assert_eq!(
+            ParsedReportDescriptor::parse(&[0xa1, 0x01]),
+            Err(ReportDescriptorError::UnbalancedCollection)
+        );
+        assert_eq!(
+            ParsedReportDescriptor::parse(&[0xc0]),
+            Err(ReportDescriptorError::UnbalancedCollection)
+        );
+        assert_eq!(
+            ParsedReportDescriptor::parse(&[0x26, 0x00]),
+            Err(ReportDescriptorError::Truncated)
+        );
+        assert_eq!(
+            ParsedReportDescriptor::parse(&[0x85, 0x00]),
+            Err(ReportDescriptorError::InvalidReportId)
+        );
+    }
+}
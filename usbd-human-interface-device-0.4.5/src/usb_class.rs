@@ -1,8 +1,9 @@
 //! USB Class for implementing Human Interface Devices
 
-use crate::descriptor::{DescriptorType, HidProtocol, HidRequest};
+use crate::descriptor::{DescriptorType, HidProtocol, HidRequest, ReportType};
 use crate::device::{DeviceClass, DeviceHList};
 use crate::interface::{InterfaceClass, ReportDescriptor, UsbAllocatable};
+use crate::report_descriptor::ReportDescriptorError;
 use crate::UsbHidError;
 use core::cell::RefCell;
 use core::default::Default;
@@ -25,12 +26,13 @@ pub mod prelude {
     //! use usbd_human_interface_device::usb_class::prelude::*;
     //! ```
 
-    pub use crate::descriptor::{HidProtocol, InterfaceProtocol};
+    pub use crate::descriptor::{HidProtocol, InterfaceProtocol, ReportType};
     pub use crate::device::DeviceClass;
     pub use crate::interface::{
-        InBytes16, InBytes32, InBytes64, InBytes8, InNone, Interface, InterfaceBuilder,
-        InterfaceConfig, OutBytes16, OutBytes32, OutBytes64, OutBytes8, OutNone, ReportSingle,
-        Reports128, Reports16, Reports32, Reports64, Reports8, UsbAllocatable,
+        FeatureBytes16, FeatureBytes32, FeatureBytes64, FeatureBytes8, FeatureNone, InBytes16,
+        InBytes32, InBytes64, InBytes8, InNone, Interface, InterfaceBuilder, InterfaceConfig,
+        OutBytes16, OutBytes32, OutBytes64, OutBytes8, OutNone, ReportSingle, Reports128,
+        Reports16, Reports32, Reports64, Reports8, UsbAllocatable,
     };
     pub use crate::interface::{ManagedIdleInterface, ManagedIdleInterfaceConfig};
     pub use crate::usb_class::{UsbHidClass, UsbHidClassBuilder};
@@ -45,6 +47,12 @@ pub enum UsbHidBuilderError {
     ValueOverflow,
     /// A slice of data is longer than permitted
     SliceLengthOverflow,
+    /// The report descriptor is malformed
+    InvalidReportDescriptor(ReportDescriptorError),
+    /// A report of the report descriptor does not fit the interface buffers
+    ReportLengthOverflow,
+    /// The report descriptor has more report IDs than the interface keeps reports for
+    TooManyReportIds,
 }
 
 /// Builder for [`UsbHidClass`]
@@ -135,6 +143,34 @@ impl<'a, B, Devices: DeviceHList<'a>> UsbHidClass<'a, B, Devices> {
 }
 
 impl<'a, B: UsbBus + 'a, Devices> UsbHidClass<'a, B, Devices> {
+    fn get_report(transfer: ControlIn<B>, interface: &mut dyn InterfaceClass<'a>) {
+        let request: &Request = transfer.request();
+        let report_id = (request.value & 0xFF) as u8;
+        let Ok(report_type) = ReportType::try_from((request.value >> 8) as u8) else {
+            warn!(
+                "Unsupported GetReport report type, value: {}",
+                request.value
+            );
+            transfer.reject().ok();
+            return;
+        };
+
+        let requested_n = request.length.into();
+        if let Err(e) = transfer.accept(|buffer| {
+            interface
+                .get_report(report_type, report_id, buffer)
+                .inspect(|&n| {
+                    if n != requested_n {
+                        warn!("GetReport requested {} bytes, got {} bytes", requested_n, n);
+                    }
+                })
+        }) {
+            error!("Failed to send report - {:?}", e);
+        } else {
+            trace!("Sent report");
+        }
+    }
+
     fn get_descriptor(transfer: ControlIn<B>, interface: &mut dyn InterfaceClass<'a>) {
         let request: &Request = transfer.request();
         match DescriptorType::try_from((request.value >> 8) as u8) {
@@ -226,8 +262,24 @@ where
 
         match HidRequest::try_from(request.request) {
             Ok(HidRequest::SetReport) => {
-                interface.set_report(transfer.data()).ok();
-                transfer.accept().ok();
+                let report_id = (request.value & 0xFF) as u8;
+                let Ok(report_type) = ReportType::try_from((request.value >> 8) as u8) else {
+                    warn!(
+                        "Unsupported SetReport report type, value: {}",
+                        request.value
+                    );
+                    transfer.reject().ok();
+                    return;
+                };
+
+                if interface
+                    .set_report(report_type, report_id, transfer.data())
+                    .is_ok()
+                {
+                    transfer.accept().ok();
+                } else {
+                    transfer.reject().ok();
+                }
             }
             Ok(HidRequest::SetIdle) => {
                 if request.length != 0 {
@@ -301,23 +353,7 @@ where
 
                 match HidRequest::try_from(request.request) {
                     Ok(HidRequest::GetReport) => {
-                        let requested_n = transfer.request().length.into();
-                        if let Err(e) = transfer.accept(|buffer| {
-                            interface.get_report(buffer).map(|n| {
-                                if n != requested_n {
-                                    warn!(
-                                        "GetReport requested {} bytes, got {} bytes",
-                                        requested_n, n
-                                    );
-                                }
-                                n
-                            })
-                        }) {
-                            error!("Failed to send report - {:?}", e);
-                        } else {
-                            trace!("Sent report");
-                            unwrap!(interface.get_report_ack());
-                        }
+                        Self::get_report(transfer, interface);
                     }
                     Ok(HidRequest::GetIdle) => {
                         if request.length != 1 {
@@ -385,7 +421,11 @@ mod test {
     use std::vec::Vec;
 
     use crate::descriptor::USB_CLASS_HID;
-    use crate::interface::{InBytes64, InterfaceBuilder, OutBytes64, ReportSingle, Reports8};
+    use crate::interface::{
+        FeatureBytes8, FeatureNone, InBytes64, InBytes8, Interface, InterfaceBuilder, OutBytes64,
+        OutBytes8, ReportSingle, Reports8,
+    };
+    use crate::report_descriptor::HidReport;
     use env_logger::Env;
     use fugit::MillisDurationU32;
     use log::SetLoggerError;
@@ -525,6 +565,32 @@ mod test {
         length: u16,
     }
 
+    /// Input and output reports with IDs 1 and 2
+    #[rustfmt::skip]
+    const TWO_REPORT_IDS_DESCRIPTOR: &[u8] = &[
+        0x05, 0x01,       // Usage Page (Generic Desktop)
+        0x09, 0x04,       // Usage (Joystick)
+        0xa1, 0x01,       // Collection (Application)
+        0x15, 0x00,       //   Logical Minimum (0)
+        0x26, 0xff, 0x00, //   Logical Maximum (255)
+        0x75, 0x08,       //   Report Size (8)
+        0x95, 0x01,       //   Report Count (1)
+        0x85, 0x01,       //   Report ID (1)
+        0x09, 0x30,       //   Usage (X)
+        0x81, 0x02,       //   Input (Data, Variable, Absolute)
+        0x09, 0x31,       //   Usage (Y)
+        0x91, 0x02,       //   Output (Data, Variable, Absolute)
+        0x85, 0x02,       //   Report ID (2)
+        0x09, 0x32,       //   Usage (Z)
+        0x81, 0x02,       //   Input (Data, Variable, Absolute)
+        0x09, 0x33,       //   Usage (Rx)
+        0x91, 0x02,       //   Output (Data, Variable, Absolute)
+        0xc0,             // End Collection
+    ];
+
+    type TwoReportIdsInterface<'a> =
+        Interface<'a, TestUsbBus<'a>, InBytes8, OutBytes8, Reports8, FeatureNone, 2>;
+
     #[test]
     fn descriptor_ordering_satisfies_boot_spec() {
         init_logging();
@@ -537,7 +603,8 @@ mod test {
             .add_device(
                 InterfaceBuilder::<InBytes64, OutBytes64, ReportSingle>::new(&[])
                     .unwrap()
-                    .build(),
+                    .build()
+                    .unwrap(),
             )
             .build(&usb_alloc);
 
@@ -632,7 +699,8 @@ mod test {
             .add_device(
                 InterfaceBuilder::<InBytes64, OutBytes64, ReportSingle>::new(&[])
                     .unwrap()
-                    .build(),
+                    .build()
+                    .unwrap(),
             )
             .build(&usb_alloc);
 
@@ -680,7 +748,8 @@ mod test {
             .add_device(
                 InterfaceBuilder::<InBytes64, OutBytes64, ReportSingle>::new(&[])
                     .unwrap()
-                    .build(),
+                    .build()
+                    .unwrap(),
             )
             .build(&usb_alloc);
 
@@ -747,7 +816,8 @@ mod test {
             .add_device(
                 InterfaceBuilder::<InBytes64, OutBytes64, ReportSingle>::new(&[])
                     .unwrap()
-                    .build(),
+                    .build()
+                    .unwrap(),
             )
             .build(&usb_alloc);
 
@@ -821,7 +891,8 @@ mod test {
                     .unwrap()
                     .idle_default(IDLE_DEFAULT)
                     .unwrap()
-                    .build(),
+                    .build()
+                    .unwrap(),
             )
             .build(&usb_alloc);
 
@@ -874,7 +945,8 @@ mod test {
                     .unwrap()
                     .idle_default(IDLE_DEFAULT)
                     .unwrap()
-                    .build(),
+                    .build()
+                    .unwrap(),
             )
             .build(&usb_alloc);
 
@@ -946,7 +1018,8 @@ mod test {
                     .unwrap()
                     .idle_default(IDLE_DEFAULT)
                     .unwrap()
-                    .build(),
+                    .build()
+                    .unwrap(),
             )
             .build(&usb_alloc);
 
@@ -1022,7 +1095,8 @@ mod test {
                     .unwrap()
                     .idle_default(IDLE_DEFAULT)
                     .unwrap()
-                    .build(),
+                    .build()
+                    .unwrap(),
             )
             .build(&usb_alloc);
 
@@ -1076,7 +1150,8 @@ mod test {
                     .unwrap()
                     .idle_default(IDLE_DEFAULT)
                     .unwrap()
-                    .build(),
+                    .build()
+                    .unwrap(),
             )
             .build(&usb_alloc);
 
@@ -1177,7 +1252,8 @@ mod test {
                     .unwrap()
                     .idle_default(IDLE_DEFAULT)
                     .unwrap()
-                    .build(),
+                    .build()
+                    .unwrap(),
             )
             .build(&usb_alloc);
 
@@ -1277,7 +1353,8 @@ mod test {
                     .unwrap()
                     .idle_default(IDLE_DEFAULT)
                     .unwrap()
-                    .build(),
+                    .build()
+                    .unwrap(),
             )
             .build(&usb_alloc);
 
@@ -1334,4 +1411,245 @@ mod test {
             "Unexpected report idle value"
         );
     }
+
+    #[test]
+    fn get_report_returns_latest_input_report() {
+        init_logging();
+
+        let manager = UsbTestManager::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let mut hid = UsbHidClassBuilder::new()
+            .add_device(
+                InterfaceBuilder::<InBytes64, OutBytes64, ReportSingle>::new(&[])
+                    .unwrap()
+                    .build()
+                    .unwrap(),
+            )
+            .build(&usb_alloc);
+
+        let mut usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+            .device_class(USB_CLASS_HID)
+            .build();
+
+        let interface =
+            hid.device::<Interface<'_, TestUsbBus<'_>, InBytes64, OutBytes64, ReportSingle>, _>();
+
+        // Reports sent on the in endpoint
+        interface.write_report(&[0x1]).unwrap();
+        assert_eq!(manager.host_read_in(), [0x1]);
+        interface.write_report(&[0x2]).unwrap();
+        assert_eq!(manager.host_read_in(), [0x2]);
+
+        // Get input report, twice
+        for _ in 0..2 {
+            manager
+                .host_write_setup(
+                    &UsbRequest {
+                        direction: UsbDirection::In != UsbDirection::Out,
+                        request_type: RequestType::Class as u8,
+                        recipient: Recipient::Interface as u8,
+                        request: HidRequest::GetReport.into(),
+                        value: u16::from(u8::from(ReportType::Input)) << 8,
+                        index: 0x0,
+                        length: 0x1,
+                    }
+                    .pack()
+                    .unwrap(),
+                )
+                .unwrap();
+
+            assert!(usb_dev.poll(&mut [&mut hid]));
+
+            let data = manager.host_read_in();
+            assert_eq!(data, [0x2], "Expected the latest input report");
+        }
+    }
+
+    #[derive(Clone, Copy, Debug, Default, Eq, PartialEq, HidReport)]
+    #[hid(usage = Desktop::Joystick, report_id = 1, feature)]
+    struct FeatureReport {
+        #[hid(usage = Desktop::Dial)]
+        dial: u8,
+    }
+
+    #[test]
+    fn feature_report_routed_by_id() {
+        init_logging();
+
+        let manager = UsbTestManager::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let mut hid = UsbHidClassBuilder::new()
+            .add_device(
+                InterfaceBuilder::<InBytes64, OutBytes64, ReportSingle, FeatureBytes8>::new(
+                    FeatureReport::DESCRIPTOR,
+                )
+                .unwrap()
+                .build()
+                .unwrap(),
+            )
+            .build(&usb_alloc);
+
+        let mut usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+            .device_class(USB_CLASS_HID)
+            .build();
+
+        let interface = hid.device::<Interface<
+            '_,
+            TestUsbBus<'_>,
+            InBytes64,
+            OutBytes64,
+            ReportSingle,
+            FeatureBytes8,
+        >, _>();
+
+        // Set by the host, read once by the device
+        interface
+            .set_report(ReportType::Feature, 1, &[1, 0x2a])
+            .unwrap();
+        let mut data = [0; 8];
+        assert!(matches!(interface.read_feature_report(1, &mut data), Ok(2)));
+        assert_eq!(data[..2], [1, 0x2a]);
+        assert!(matches!(
+            interface.read_feature_report(1, &mut data),
+            Err(UsbError::WouldBlock)
+        ));
+        assert!(matches!(
+            interface.set_report(ReportType::Input, 1, &[1, 0x2a]),
+            Err(UsbError::Unsupported)
+        ));
+
+        interface.write_feature_report(1, &[1, 0x17]).unwrap();
+
+        // Get feature report
+        manager
+            .host_write_setup(
+                &UsbRequest {
+                    direction: UsbDirection::In != UsbDirection::Out,
+                    request_type: RequestType::Class as u8,
+                    recipient: Recipient::Interface as u8,
+                    request: HidRequest::GetReport.into(),
+                    value: u16::from(u8::from(ReportType::Feature)) << 8 | 1,
+                    index: 0x0,
+                    length: 0x2,
+                }
+                .pack()
+                .unwrap(),
+            )
+            .unwrap();
+
+        assert!(usb_dev.poll(&mut [&mut hid]));
+
+        let data = manager.host_read_in();
+        assert_eq!(data, [1, 0x17], "Unexpected feature report");
+    }
+
+    #[test]
+    fn too_many_report_ids() {
+        init_logging();
+
+        let result =
+            InterfaceBuilder::<InBytes8, OutBytes8, Reports8>::new(TWO_REPORT_IDS_DESCRIPTOR)
+                .unwrap()
+                .build();
+
+        assert!(matches!(result, Err(UsbHidBuilderError::TooManyReportIds)));
+    }
+
+    #[test]
+    fn output_reports_routed_by_id() {
+        init_logging();
+
+        let manager = UsbTestManager::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let mut hid = UsbHidClassBuilder::new()
+            .add_device(
+                InterfaceBuilder::<InBytes8, OutBytes8, Reports8, FeatureNone, 2>::new(
+                    TWO_REPORT_IDS_DESCRIPTOR,
+                )
+                .unwrap()
+                .build()
+                .unwrap(),
+            )
+            .build(&usb_alloc);
+
+        let interface = hid.device::<TwoReportIdsInterface<'_>, _>();
+
+        interface
+            .set_report(ReportType::Output, 1, &[1, 0x11])
+            .unwrap();
+        interface
+            .set_report(ReportType::Output, 2, &[2, 0x22])
+            .unwrap();
+        interface
+            .set_report(ReportType::Output, 1, &[1, 0x12])
+            .unwrap();
+
+        let mut data = [0; 8];
+        assert!(matches!(interface.read_report(&mut data), Ok(2)));
+        assert_eq!(data[..2], [1, 0x12], "Expected the latest report of ID 1");
+        assert!(matches!(interface.read_report(&mut data), Ok(2)));
+        assert_eq!(data[..2], [2, 0x22], "Expected the report of ID 2");
+        assert!(matches!(
+            interface.read_report(&mut data),
+            Err(UsbError::WouldBlock)
+        ));
+    }
+
+    #[test]
+    fn get_report_returns_latest_input_report_of_id() {
+        init_logging();
+
+        let manager = UsbTestManager::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let mut hid = UsbHidClassBuilder::new()
+            .add_device(
+                InterfaceBuilder::<InBytes8, OutBytes8, Reports8, FeatureNone, 2>::new(
+                    TWO_REPORT_IDS_DESCRIPTOR,
+                )
+                .unwrap()
+                .build()
+                .unwrap(),
+            )
+            .build(&usb_alloc);
+
+        let mut usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+            .device_class(USB_CLASS_HID)
+            .build();
+
+        let interface = hid.device::<TwoReportIdsInterface<'_>, _>();
+
+        // Reports sent on the in endpoint
+        for report in [[1, 0x11], [2, 0x22], [1, 0x12]] {
+            interface.write_report(&report).unwrap();
+            assert_eq!(manager.host_read_in(), report);
+        }
+
+        // Get input report of each ID
+        for (report_id, expected) in [(1, [1, 0x12]), (2, [2, 0x22])] {
+            manager
+                .host_write_setup(
+                    &UsbRequest {
+                        direction: UsbDirection::In != UsbDirection::Out,
+                        request_type: RequestType::Class as u8,
+                        recipient: Recipient::Interface as u8,
+                        request: HidRequest::GetReport.into(),
+                        value: u16::from(u8::from(ReportType::Input)) << 8 | report_id,
+                        index: 0x0,
+                        length: 0x2,
+                    }
+                    .pack()
+                    .unwrap(),
+                )
+                .unwrap();
+
+            assert!(usb_dev.poll(&mut [&mut hid]));
+
+            let data = manager.host_read_in();
+            assert_eq!(data, expected, "Expected the latest input report of the ID");
+        }
+    }
 }
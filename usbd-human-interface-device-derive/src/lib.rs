@@ -0,0 +1,1089 @@
+//! Derive macro for [usbd-human-interface-device](https://crates.io/crates/usbd-human-interface-device)
+//! reports
+//!
+//! `#[derive(HidReport)]` generates the `PackedStruct` implementation of a report struct and its
+//! matching report descriptor from `#[hid(...)]` attributes, see
+//! `usbd_human_interface_device::report_descriptor::HidReport` for the attributes.
+#![warn(clippy::pedantic)]
+
+use proc_macro2::{Literal, Span, TokenStream};
+use quote::{quote, ToTokens};
+use syn::meta::ParseNestedMeta;
+use syn::parse::{Parse, ParseStream};
+use syn::spanned::Spanned;
+use syn::token::Paren;
+use syn::{
+    parenthesized, parse_macro_input, Attribute, Data, DeriveInput, Error, Expr, ExprLit, Fields,
+    Ident, Lit, LitInt, Result, Token, Type,
+};
+
+/// Usage pages with enums in `usbd_human_interface_device::page`
+const ENUM_PAGES: [&str; 7] = [
+    "Desktop",
+    "Simulation",
+    "Game",
+    "Keyboard",
+    "Leds",
+    "Telephony",
+    "Consumer",
+];
+/// Usage pages whose usages are numbers
+const NUMBERED_PAGES: [&str; 2] = ["Button", "Ordinal"];
+/// Collections of fields, the application collection is opened by the struct
+const COLLECTIONS: [&str; 6] = [
+    "Physical",
+    "Logical",
+    "Report",
+    "NamedArray",
+    "UsageSwitch",
+    "UsageModifier",
+];
+
+const CONSTANT: u16 = 0x01;
+const VARIABLE: u16 = 0x02;
+const RELATIVE: u16 = 0x04;
+const NULL_STATE: u16 = 0x40;
+
+/// Bytes of the longest item
+const MAX_ITEM_LEN: usize = 5;
+
+#[proc_macro_derive(HidReport, attributes(hid))]
+pub fn derive_hid_report(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    parse_report(&input)
+        .and_then(|report| report.expand())
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn krate() -> TokenStream {
+    quote!(::usbd_human_interface_device)
+}
+
+/// Usage together with its page, e.g. `Desktop::X` or `Button(1)`
+#[derive(Clone, PartialEq, Eq)]
+struct UsageSpec {
+    page: Ident,
+    id: UsageId,
+}
+
+#[derive(Clone, PartialEq, Eq)]
+enum UsageId {
+    Named(Ident),
+    Numbered(u16),
+}
+
+impl Parse for UsageSpec {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let page: Ident = input.parse()?;
+        let name = page.to_string();
+        if input.peek(Token![::]) {
+            if !ENUM_PAGES.contains(&name.as_str()) {
+                return Err(Error::new(
+                    page.span(),
+                    format!("unknown usage page `{page}`"),
+                ));
+            }
+            input.parse::<Token![::]>()?;
+            Ok(Self {
+                page,
+                id: UsageId::Named(input.parse()?),
+            })
+        } else if input.peek(Paren) && NUMBERED_PAGES.contains(&name.as_str()) {
+            let content;
+            parenthesized!(content in input);
+            let id: LitInt = content.parse()?;
+            let id = id
+                .base10_parse()
+                .map_err(|_| Error::new(id.span(), "usage IDs are 16 bits"))?;
+            Ok(Self {
+                page,
+                id: UsageId::Numbered(id),
+            })
+        } else {
+            Err(Error::new(
+                page.span(),
+                "expected a usage such as `Desktop::X` or `Button(1)`",
+            ))
+        }
+    }
+}
+
+impl ToTokens for UsageSpec {
+    /// `Usage` expression
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let krate = krate();
+        let page = &self.page;
+        tokens.extend(match &self.id {
+            UsageId::Named(usage) => {
+                quote!(#krate::report_descriptor::Usage::#page(#krate::page::#page::#usage))
+            }
+            UsageId::Numbered(id) => quote!(#krate::report_descriptor::Usage::#page(#id)),
+        });
+    }
+}
+
+#[derive(Clone, PartialEq, Eq)]
+struct CollectionSpec {
+    kind: Ident,
+    usage: Option<UsageSpec>,
+}
+
+impl Parse for CollectionSpec {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let kind: Ident = input.parse()?;
+        if !COLLECTIONS.contains(&kind.to_string().as_str()) {
+            return Err(Error::new(
+                kind.span(),
+                format!("unknown collection `{kind}`"),
+            ));
+        }
+        let usage = if input.peek(Paren) {
+            let content;
+            parenthesized!(content in input);
+            Some(content.parse()?)
+        } else {
+            None
+        };
+        Ok(Self { kind, usage })
+    }
+}
+
+#[derive(Clone, PartialEq, Eq)]
+enum Usages {
+    Single(UsageSpec),
+    Range(UsageSpec, UsageSpec),
+}
+
+impl Usages {
+    fn page(&self) -> &Ident {
+        match self {
+            Self::Single(usage) | Self::Range(usage, _) => &usage.page,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Scalar {
+    bits: u32,
+    signed: bool,
+}
+
+struct Field {
+    name: Ident,
+    /// Array length, `None` for scalar fields
+    len: Option<u32>,
+    usages: Usages,
+    logical_min: i32,
+    logical_max: i32,
+    /// Bits of every report field
+    size: u32,
+    /// Report fields, the elements of an array or bit groups of a scalar
+    count: u32,
+    flags: u16,
+    collection: Option<CollectionSpec>,
+    /// Constant bits after the field
+    padding: u32,
+}
+
+impl Field {
+    fn bits(&self) -> u32 {
+        self.size * self.count
+    }
+
+    /// Whether `next` can share the Input item of `self`, one usage each
+    fn merges_with(&self, next: &Self) -> bool {
+        matches!(
+            (&self.usages, &next.usages),
+            (Usages::Single(_), Usages::Single(_))
+        ) && self.len.is_none()
+            && next.len.is_none()
+            && self.count == 1
+            && next.count == 1
+            && self.padding == 0
+            && self.usages.page() == next.usages.page()
+            && self.logical_min == next.logical_min
+            && self.logical_max == next.logical_max
+            && self.size == next.size
+            && self.flags == next.flags
+            && self.collection == next.collection
+    }
+}
+
+struct Report {
+    name: Ident,
+    usage: UsageSpec,
+    id: Option<u8>,
+    /// Main item of the fields: `input`, `output` or `feature`
+    main_item: &'static str,
+    fields: Vec<Field>,
+}
+
+/// The `#[hid(...)]` attributes, reports and fields need at least one
+fn hid_attributes(attributes: &[Attribute]) -> Vec<&Attribute> {
+    attributes
+        .iter()
+        .filter(|attribute| attribute.path().is_ident("hid"))
+        .collect()
+}
+
+fn key(meta: &ParseNestedMeta) -> String {
+    meta.path.to_token_stream().to_string().replace(' ', "")
+}
+
+/// Attribute without a value, e.g. `relative`
+fn flag(meta: &ParseNestedMeta) -> Result<()> {
+    if meta.input.is_empty() || meta.input.peek(Token![,]) {
+        Ok(())
+    } else {
+        Err(meta.error(format!("`{}` takes no value", key(meta))))
+    }
+}
+
+/// Integer such as `-100` or `0x3ff`
+fn parse_int(input: ParseStream) -> Result<i64> {
+    let negative = input.parse::<Option<Token![-]>>()?.is_some();
+    let value: i64 = input.parse::<LitInt>()?.base10_parse()?;
+    Ok(if negative { -value } else { value })
+}
+
+fn unsigned(meta: &ParseNestedMeta, max: u32) -> Result<u32> {
+    let value = parse_int(meta.value()?)?;
+    u32::try_from(value)
+        .ok()
+        .filter(|value| (1..=max).contains(value))
+        .ok_or_else(|| meta.error(format!("`{}` must be in `1..={max}`", key(meta))))
+}
+
+fn parse_scalar(ty: &Type) -> Option<Scalar> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    if path.qself.is_some() {
+        return None;
+    }
+    let (bits, signed) = match path.path.get_ident()?.to_string().as_str() {
+        "bool" => (1, false),
+        "u8" => (8, false),
+        "u16" => (16, false),
+        "u32" => (32, false),
+        "i8" => (8, true),
+        "i16" => (16, true),
+        "i32" => (32, true),
+        _ => return None,
+    };
+    Some(Scalar { bits, signed })
+}
+
+/// Element type and array length of a field type
+fn parse_type(ty: &Type) -> Result<(Scalar, Option<u32>)> {
+    let parsed = match ty {
+        Type::Array(array) => match &array.len {
+            Expr::Lit(ExprLit {
+                lit: Lit::Int(len), ..
+            }) => parse_scalar(&array.elem)
+                .zip(len.base10_parse().ok().filter(|&len| len > 0))
+                .map(|(scalar, len)| (scalar, Some(len))),
+            _ => None,
+        },
+        ty => parse_scalar(ty).map(|scalar| (scalar, None)),
+    };
+    parsed.ok_or_else(|| {
+        Error::new(
+            ty.span(),
+            "report fields must be `bool`, `u8`, `u16`, `u32`, `i8`, `i16`, `i32` or arrays of them",
+        )
+    })
+}
+
+#[allow(clippy::too_many_lines)]
+fn parse_field(field: &syn::Field) -> Result<Field> {
+    let name = field
+        .ident
+        .clone()
+        .ok_or_else(|| Error::new(field.span(), "expected a named field"))?;
+    let (scalar, len) = parse_type(&field.ty)?;
+    let attributes = hid_attributes(&field.attrs);
+    if attributes.is_empty() {
+        return Err(Error::new(
+            name.span(),
+            "report fields need a `#[hid(...)]` attribute with their usage",
+        ));
+    }
+
+    let mut usage = None;
+    let mut usage_min: Option<UsageSpec> = None;
+    let mut usage_max: Option<UsageSpec> = None;
+    let mut logical_min = None;
+    let mut logical_max = None;
+    let mut size = None;
+    let mut count = None;
+    let mut flags = VARIABLE;
+    let mut collection = None;
+    let mut padding = 0;
+    for attribute in attributes {
+        attribute.parse_nested_meta(|meta| {
+            match key(&meta).as_str() {
+                "usage" => usage = Some(meta.value()?.parse()?),
+                "usage_min" => usage_min = Some(meta.value()?.parse()?),
+                "usage_max" => usage_max = Some(meta.value()?.parse()?),
+                "logical_min" => logical_min = Some(parse_int(meta.value()?)?),
+                "logical_max" => logical_max = Some(parse_int(meta.value()?)?),
+                "size" => size = Some(unsigned(&meta, scalar.bits)?),
+                "count" if len.is_some() => {
+                    return Err(meta.error("the count of an array field is its length"))
+                }
+                "count" => count = Some(unsigned(&meta, scalar.bits)?),
+                "padding" => padding = unsigned(&meta, u32::from(u8::MAX))?,
+                "collection" => collection = Some(meta.value()?.parse()?),
+                "relative" => {
+                    flag(&meta)?;
+                    flags |= RELATIVE;
+                }
+                "null_state" => {
+                    flag(&meta)?;
+                    flags |= NULL_STATE;
+                }
+                key => return Err(meta.error(format!("unknown field attribute `{key}`"))),
+            }
+            Ok(())
+        })?;
+    }
+
+    let usages = match (usage, usage_min, usage_max) {
+        (Some(usage), None, None) => Usages::Single(usage),
+        (None, Some(min), Some(max)) if min.page == max.page => Usages::Range(min, max),
+        (None, Some(_), Some(_)) => {
+            return Err(Error::new(
+                name.span(),
+                "`usage_min` and `usage_max` must be on the same usage page",
+            ))
+        }
+        _ => {
+            return Err(Error::new(
+                name.span(),
+                "fields need either `usage` or both `usage_min` and `usage_max`",
+            ))
+        }
+    };
+
+    let count = len.or(count).unwrap_or(1);
+    let size = match size {
+        Some(size) => size,
+        None if len.is_some() => scalar.bits,
+        None => scalar.bits / count,
+    };
+    if len.is_none() && size * count > scalar.bits {
+        return Err(Error::new(
+            name.span(),
+            format!(
+                "{count} fields of {size} bits do not fit a {} bit field",
+                scalar.bits
+            ),
+        ));
+    }
+
+    let (default_min, default_max) = if scalar.signed {
+        (-(1 << (size - 1)), (1 << (size - 1)) - 1)
+    } else {
+        (0, (1 << size) - 1)
+    };
+    let (Ok(logical_min), Ok(logical_max)) = (
+        i32::try_from(logical_min.unwrap_or(default_min)),
+        i32::try_from(logical_max.unwrap_or(default_max)),
+    ) else {
+        return Err(Error::new(
+            name.span(),
+            "logical range does not fit an `i32`, set `logical_min` and `logical_max`",
+        ));
+    };
+    if logical_min > logical_max {
+        return Err(Error::new(
+            name.span(),
+            "`logical_min` is above `logical_max`",
+        ));
+    }
+
+    Ok(Field {
+        name,
+        len,
+        usages,
+        logical_min,
+        logical_max,
+        size,
+        count,
+        flags,
+        collection,
+        padding,
+    })
+}
+
+fn parse_report(input: &DeriveInput) -> Result<Report> {
+    let name = &input.ident;
+    if !input.generics.params.is_empty() {
+        return Err(Error::new(
+            input.generics.span(),
+            "HidReport can not be derived for generic structs",
+        ));
+    }
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new(
+            name.span(),
+            "HidReport can only be derived for structs with named fields",
+        ));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(Error::new(
+            name.span(),
+            "HidReport can only be derived for structs with named fields",
+        ));
+    };
+
+    let attributes = hid_attributes(&input.attrs);
+    if attributes.is_empty() {
+        return Err(Error::new(
+            name.span(),
+            "reports need a `#[hid(usage = ...)]` attribute with their application usage",
+        ));
+    }
+    let mut usage = None;
+    let mut report_id = None;
+    let mut main_item = "input";
+    for attribute in attributes {
+        attribute.parse_nested_meta(|meta| {
+            match key(&meta).as_str() {
+                "usage" => usage = Some(meta.value()?.parse()?),
+                "report_id" => {
+                    report_id = u8::try_from(unsigned(&meta, u32::from(u8::MAX))?).ok();
+                }
+                "output" => {
+                    flag(&meta)?;
+                    main_item = "output";
+                }
+                "feature" => {
+                    flag(&meta)?;
+                    main_item = "feature";
+                }
+                key => return Err(meta.error(format!("unknown report attribute `{key}`"))),
+            }
+            Ok(())
+        })?;
+    }
+    let usage = usage.ok_or_else(|| {
+        Error::new(
+            name.span(),
+            "reports need a `usage`, the usage of their application collection",
+        )
+    })?;
+
+    let fields = fields
+        .named
+        .iter()
+        .map(parse_field)
+        .collect::<Result<Vec<_>>>()?;
+    if fields.is_empty() {
+        return Err(Error::new(name.span(), "reports need at least one field"));
+    }
+
+    Ok(Report {
+        name: name.clone(),
+        usage,
+        id: report_id,
+        main_item,
+        fields,
+    })
+}
+
+/// Builder method calls of a report descriptor
+#[derive(Default)]
+struct Descriptor {
+    items: TokenStream,
+    /// Upper bound of the descriptor length
+    capacity: usize,
+    usage_page: Option<Ident>,
+}
+
+impl Descriptor {
+    fn item(&mut self, method: &str, argument: impl ToTokens) {
+        let method = Ident::new(method, Span::call_site());
+        self.items.extend(quote!(.#method(#argument)));
+        self.capacity += MAX_ITEM_LEN;
+    }
+
+    /// Local usage item, switching the usage page when needed
+    fn usage(&mut self, method: &str, usage: &UsageSpec) {
+        if self.usage_page.as_ref() != Some(&usage.page) {
+            let krate = krate();
+            let page = &usage.page;
+            self.item(
+                "usage_page",
+                quote!(#krate::report_descriptor::UsagePage::#page),
+            );
+            self.usage_page = Some(page.clone());
+        }
+        self.item(method, usage);
+    }
+
+    fn collection(&mut self, kind: &Ident) {
+        let krate = krate();
+        self.item(
+            "collection",
+            quote!(#krate::report_descriptor::Collection::#kind),
+        );
+    }
+
+    fn main_item(&mut self, method: &str, flags: u16) {
+        let krate = krate();
+        self.item(method, quote!(#krate::report_descriptor::MainFlags(#flags)));
+    }
+
+    fn end_collection(&mut self) {
+        self.item("end_collection", TokenStream::new());
+    }
+}
+
+impl Report {
+    /// Fields sharing a main item
+    fn groups(&self) -> Vec<&[Field]> {
+        let mut groups = Vec::new();
+        let mut start = 0;
+        for end in 1..=self.fields.len() {
+            if end == self.fields.len() || !self.fields[end - 1].merges_with(&self.fields[end]) {
+                groups.push(&self.fields[start..end]);
+                start = end;
+            }
+        }
+        groups
+    }
+
+    fn descriptor(&self) -> Descriptor {
+        let mut descriptor = Descriptor::default();
+        descriptor.usage("usage", &self.usage);
+        descriptor.collection(&Ident::new("Application", Span::call_site()));
+        if let Some(id) = self.id {
+            descriptor.item("report_id", id);
+        }
+
+        let mut collection = None;
+        for group in self.groups() {
+            let first = &group[0];
+            if first.collection.as_ref() != collection {
+                if collection.is_some() {
+                    descriptor.end_collection();
+                }
+                if let Some(spec) = &first.collection {
+                    if let Some(usage) = &spec.usage {
+                        descriptor.usage("usage", usage);
+                    }
+                    descriptor.collection(&spec.kind);
+                }
+                collection = first.collection.as_ref();
+            }
+
+            match &first.usages {
+                Usages::Single(_) => {
+                    for field in group {
+                        if let Usages::Single(usage) = &field.usages {
+                            descriptor.usage("usage", usage);
+                        }
+                    }
+                }
+                Usages::Range(min, max) => {
+                    descriptor.usage("usage_minimum", min);
+                    descriptor.usage("usage_maximum", max);
+                }
+            }
+            let count: u32 = group.iter().map(|field| field.count).sum();
+            descriptor.item("logical_minimum", first.logical_min);
+            descriptor.item("logical_maximum", first.logical_max);
+            descriptor.item("report_size", first.size);
+            descriptor.item("report_count", count);
+            descriptor.main_item(self.main_item, first.flags);
+
+            let padding = group[group.len() - 1].padding;
+            if padding > 0 {
+                descriptor.item("report_size", padding);
+                descriptor.item("report_count", 1u32);
+                descriptor.main_item(self.main_item, CONSTANT | VARIABLE);
+            }
+        }
+
+        if collection.is_some() {
+            descriptor.end_collection();
+        }
+        descriptor.end_collection();
+        descriptor
+    }
+
+    fn expand(&self) -> Result<TokenStream> {
+        let krate = krate();
+        let fields = quote!(#krate::report_descriptor::fields);
+        // Offsets, lengths and indices are `usize` in the generated code
+        let unsuffixed = Literal::u32_unsuffixed;
+        let mut offset = if self.id.is_some() { 8 } else { 0 };
+        let mut pack = TokenStream::new();
+        let mut unpack = TokenStream::new();
+        for field in &self.fields {
+            let name = &field.name;
+            if let Some(len) = field.len {
+                let size = unsuffixed(field.size);
+                let mut elements = Vec::new();
+                for i in 0..len {
+                    let at = unsuffixed(offset + i * field.size);
+                    let index = unsuffixed(i);
+                    pack.extend(quote! {
+                        #fields::write_bits(&mut bytes, #at, #size, #fields::ReportField::to_bits(self.#name[#index]));
+                    });
+                    elements.push(quote! {
+                        #fields::ReportField::from_bits(#fields::read_bits(src, #at, #size), #size)
+                    });
+                }
+                unpack.extend(quote!(#name: [#(#elements),*],));
+            } else {
+                let at = unsuffixed(offset);
+                let bits = unsuffixed(field.bits());
+                pack.extend(quote! {
+                    #fields::write_bits(&mut bytes, #at, #bits, #fields::ReportField::to_bits(self.#name));
+                });
+                unpack.extend(quote! {
+                    #name: #fields::ReportField::from_bits(#fields::read_bits(src, #at, #bits), #bits),
+                });
+            }
+            offset += field.bits() + field.padding;
+        }
+
+        if offset % 8 != 0 {
+            return Err(Error::new(
+                self.name.span(),
+                format!("report is {offset} bits, pad it to whole bytes with `padding`"),
+            ));
+        }
+        let len = unsuffixed(offset / 8);
+        let (pack_id, check_id) = match self.id {
+            Some(id) => (
+                quote!(bytes[0] = #id;),
+                quote! {
+                    if src[0] != #id {
+                        return Err(#fields::PackingError::InvalidValue);
+                    }
+                },
+            ),
+            None => (TokenStream::new(), TokenStream::new()),
+        };
+
+        let name = &self.name;
+        let descriptor = self.descriptor();
+        let items = descriptor.items;
+        let capacity = descriptor.capacity;
+        Ok(quote! {
+            #[automatically_derived]
+            impl #fields::PackedStruct for #name {
+                type ByteArray = [u8; #len];
+
+                fn pack(&self) -> #fields::PackingResult<[u8; #len]> {
+                    let mut bytes = [0; #len];
+                    #pack_id
+                    #pack
+                    Ok(bytes)
+                }
+
+                fn unpack(src: &[u8; #len]) -> #fields::PackingResult<Self> {
+                    #check_id
+                    Ok(Self { #unpack })
+                }
+            }
+
+            #[automatically_derived]
+            impl #krate::report_descriptor::HidReport for #name {
+                const DESCRIPTOR: &'static [u8] = {
+                    const BUFFER: #krate::report_descriptor::builder::ReportDescriptorBuf<#capacity> =
+                        #krate::report_descriptor::builder::ReportDescriptorBuilder::new() #items .finish();
+                    const BYTES: [u8; BUFFER.as_bytes().len()] = #fields::descriptor_bytes(&BUFFER);
+                    &BYTES
+                };
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use syn::parse_quote;
+
+    fn report(input: &DeriveInput) -> Report {
+        parse_report(input).unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Message of the error reported for `input`, parsing or expanding it
+    fn error(input: &DeriveInput) -> String {
+        match parse_report(input).and_then(|report| report.expand()) {
+            Ok(_) => panic!("`{}` has no error", input.to_token_stream()),
+            Err(error) => error.to_string(),
+        }
+    }
+
+    /// Builder methods called by the report descriptor, in order
+    fn items(report: &Report) -> Vec<String> {
+        let mut items = Vec::new();
+        let mut after_dot = false;
+        for token in report.descriptor().items {
+            match token {
+                proc_macro2::TokenTree::Punct(punct) => after_dot = punct.as_char() == '.',
+                proc_macro2::TokenTree::Ident(ident) if after_dot => {
+                    items.push(ident.to_string());
+                    after_dot = false;
+                }
+                _ => after_dot = false,
+            }
+        }
+        items
+    }
+
+    fn expanded(report: &Report) -> String {
+        report
+            .expand()
+            .unwrap_or_else(|error| panic!("{error}"))
+            .to_string()
+    }
+
+    #[test]
+    fn array_fields() {
+        let report = report(&parse_quote! {
+            #[hid(usage = Desktop::Joystick)]
+            struct Report {
+                #[hid(usage_min = Button(1), usage_max = Button(8))]
+                buttons: [bool; 8],
+                #[hid(usage_min = Ordinal(1), usage_max = Ordinal(2), size = 4)]
+                nibbles: [u8; 2],
+            }
+        });
+
+        let buttons = &report.fields[0];
+        assert_eq!((buttons.len, buttons.size, buttons.count), (Some(8), 1, 8));
+        let nibbles = &report.fields[1];
+        assert_eq!((nibbles.len, nibbles.size, nibbles.count), (Some(2), 4, 2));
+        assert_eq!((nibbles.logical_min, nibbles.logical_max), (0, 15));
+        assert!(expanded(&report).contains("type ByteArray = [u8 ; 2]"));
+    }
+
+    #[test]
+    fn scalar_split_into_report_fields() {
+        let report = report(&parse_quote! {
+            #[hid(usage = Desktop::Joystick)]
+            struct Report {
+                #[hid(usage_min = Button(1), usage_max = Button(4), count = 4, padding = 4)]
+                buttons: u8,
+            }
+        });
+
+        let buttons = &report.fields[0];
+        assert_eq!((buttons.size, buttons.count, buttons.padding), (2, 4, 4));
+    }
+
+    #[test]
+    fn padding_adds_constant_item() {
+        let report = report(&parse_quote! {
+            #[hid(usage = Desktop::Joystick)]
+            struct Report {
+                #[hid(usage = Desktop::X, size = 10, padding = 6)]
+                x: u16,
+                #[hid(usage = Desktop::Y)]
+                y: u8,
+            }
+        });
+
+        assert_eq!(
+            items(&report)[3..],
+            [
+                "usage",
+                "logical_minimum",
+                "logical_maximum",
+                "report_size",
+                "report_count",
+                "input",
+                "report_size",
+                "report_count",
+                "input",
+                "usage",
+                "logical_minimum",
+                "logical_maximum",
+                "report_size",
+                "report_count",
+                "input",
+                "end_collection",
+            ]
+        );
+        assert!(expanded(&report).contains("type ByteArray = [u8 ; 3]"));
+    }
+
+    #[test]
+    fn report_id_is_first_item_and_byte() {
+        let report = report(&parse_quote! {
+            #[hid(usage = Desktop::Joystick, report_id = 3, feature)]
+            struct Report {
+                #[hid(usage = Desktop::X)]
+                x: u8,
+            }
+        });
+
+        assert_eq!(report.id, Some(3));
+        assert_eq!(report.main_item, "feature");
+        assert_eq!(
+            items(&report)[..4],
+            ["usage_page", "usage", "collection", "report_id"]
+        );
+        let expanded = expanded(&report);
+        assert!(expanded.contains("type ByteArray = [u8 ; 2]"));
+        assert!(expanded.contains("bytes [0] = 3u8"));
+    }
+
+    #[test]
+    fn consecutive_fields_share_items() {
+        let report = report(&parse_quote! {
+            #[hid(usage = Desktop::Joystick)]
+            struct Report {
+                #[hid(usage = Desktop::X)]
+                x: u8,
+                #[hid(usage = Desktop::Y)]
+                y: u8,
+                #[hid(usage = Desktop::Z, relative)]
+                z: u8,
+            }
+        });
+
+        let groups: Vec<usize> = report.groups().iter().map(|group| group.len()).collect();
+        assert_eq!(groups, [2, 1]);
+        assert_eq!(report.fields[2].flags, VARIABLE | RELATIVE);
+    }
+
+    #[test]
+    fn collections_open_once_for_consecutive_fields() {
+        let report = report(&parse_quote! {
+            #[hid(usage = Desktop::Mouse)]
+            struct Report {
+                #[hid(usage = Desktop::X, collection = Physical(Desktop::Pointer))]
+                x: i8,
+                #[hid(usage = Desktop::Y, collection = Physical(Desktop::Pointer))]
+                y: i8,
+                #[hid(usage = Desktop::Wheel, collection = Logical)]
+                wheel: i8,
+                #[hid(usage = Desktop::Dial)]
+                dial: i8,
+            }
+        });
+
+        let items = items(&report);
+        let collections: Vec<&str> = items
+            .iter()
+            .map(String::as_str)
+            .filter(|item| matches!(*item, "collection" | "end_collection"))
+            .collect();
+        assert_eq!(
+            collections,
+            [
+                "collection",
+                "collection",
+                "end_collection",
+                "collection",
+                "end_collection",
+                "end_collection",
+            ]
+        );
+    }
+
+    #[test]
+    fn report_attribute_errors() {
+        let cases: [(DeriveInput, &str); 5] = [
+            (
+                parse_quote!(
+                    struct Report {
+                        #[hid(usage = Desktop::X)]
+                        x: u8,
+                    }
+                ),
+                "reports need a `#[hid(usage = ...)]` attribute with their application usage",
+            ),
+            (
+                parse_quote!(
+                    #[hid(report_id = 1)]
+                    struct Report {
+                        #[hid(usage = Desktop::X)]
+                        x: u8,
+                    }
+                ),
+                "reports need a `usage`, the usage of their application collection",
+            ),
+            (
+                parse_quote!(
+                    #[hid(usage = Desktop::Joystick, input)]
+                    struct Report {
+                        #[hid(usage = Desktop::X)]
+                        x: u8,
+                    }
+                ),
+                "unknown report attribute `input`",
+            ),
+            (
+                parse_quote!(
+                    #[hid(usage = Desktop::Joystick, report_id = 0)]
+                    struct Report {
+                        #[hid(usage = Desktop::X)]
+                        x: u8,
+                    }
+                ),
+                "`report_id` must be in `1..=255`",
+            ),
+            (
+                parse_quote!(
+                    #[hid(usage = Desktop::Joystick, output = true)]
+                    struct Report {
+                        #[hid(usage = Desktop::X)]
+                        x: u8,
+                    }
+                ),
+                "`output` takes no value",
+            ),
+        ];
+
+        for (input, expected) in &cases {
+            assert_eq!(error(input), *expected);
+        }
+    }
+
+    #[test]
+    fn field_attribute_errors() {
+        let cases: [(DeriveInput, &str); 6] = [
+            (
+                parse_quote!(
+                    #[hid(usage = Desktop::Joystick)]
+                    struct Report {
+                        x: u8,
+                    }
+                ),
+                "report fields need a `#[hid(...)]` attribute with their usage",
+            ),
+            (
+                parse_quote!(
+                    #[hid(usage = Desktop::Joystick)]
+                    struct Report {
+                        #[hid(usage = Desktop::X, scale = 2)]
+                        x: u8,
+                    }
+                ),
+                "unknown field attribute `scale`",
+            ),
+            (
+                parse_quote!(
+                    #[hid(usage = Desktop::Joystick)]
+                    struct Report {
+                        #[hid(usage = Vendor::X)]
+                        x: u8,
+                    }
+                ),
+                "unknown usage page `Vendor`",
+            ),
+            (
+                parse_quote!(
+                    #[hid(usage = Desktop::Joystick)]
+                    struct Report {
+                        #[hid(usage_min = Button(1), usage_max = Ordinal(8))]
+                        buttons: [bool; 8],
+                    }
+                ),
+                "`usage_min` and `usage_max` must be on the same usage page",
+            ),
+            (
+                parse_quote!(
+                    #[hid(usage = Desktop::Joystick)]
+                    struct Report {
+                        #[hid(usage = Desktop::X, collection = Group)]
+                        x: u8,
+                    }
+                ),
+                "unknown collection `Group`",
+            ),
+            (
+                parse_quote!(
+                    #[hid(usage = Desktop::Joystick)]
+                    struct Report {
+                        #[hid(usage = Desktop::X, logical_min = 10, logical_max = -10)]
+                        x: i8,
+                    }
+                ),
+                "`logical_min` is above `logical_max`",
+            ),
+        ];
+
+        for (input, expected) in &cases {
+            assert_eq!(error(input), *expected);
+        }
+    }
+
+    #[test]
+    fn layout_errors() {
+        let cases: [(DeriveInput, &str); 6] = [
+            (
+                parse_quote!(
+                    #[hid(usage = Desktop::Joystick)]
+                    struct Report(u8);
+                ),
+                "HidReport can only be derived for structs with named fields",
+            ),
+            (
+                parse_quote!(
+                    #[hid(usage = Desktop::Joystick)]
+                    struct Report<T> {
+                        #[hid(usage = Desktop::X)]
+                        x: T,
+                    }
+                ),
+                "HidReport can not be derived for generic structs",
+            ),
+            (
+                parse_quote!(
+                    #[hid(usage = Desktop::Joystick)]
+                    struct Report {
+                        #[hid(usage = Desktop::X)]
+                        x: f32,
+                    }
+                ),
+                "report fields must be `bool`, `u8`, `u16`, `u32`, `i8`, `i16`, `i32` or arrays of them",
+            ),
+            (
+                parse_quote!(
+                    #[hid(usage = Desktop::Joystick)]
+                    struct Report {
+                        #[hid(usage_min = Button(1), usage_max = Button(8), count = 8)]
+                        buttons: [bool; 8],
+                    }
+                ),
+                "the count of an array field is its length",
+            ),
+            (
+                parse_quote!(
+                    #[hid(usage = Desktop::Joystick)]
+                    struct Report {
+                        #[hid(usage_min = Button(1), usage_max = Button(3), size = 4, count = 3)]
+                        buttons: u8,
+                    }
+                ),
+                "3 fields of 4 bits do not fit a 8 bit field",
+            ),
+            (
+                parse_quote!(
+                    #[hid(usage = Desktop::Joystick)]
+                    struct Report {
+                        #[hid(usage = Desktop::X, size = 7)]
+                        x: u8,
+                    }
+                ),
+                "report is 7 bits, pad it to whole bytes with `padding`",
+            ),
+        ];
+
+        for (input, expected) in &cases {
+            assert_eq!(error(input), *expected);
+        }
+    }
+}
@@ -0,0 +1,10 @@
+use usbd_human_interface_device::report_descriptor::HidReport;
+
+#[derive(HidReport)]
+#[hid(usage = Desktop::Joystick)]
+struct Report {
+    #[hid(usage_min = Button(1), usage_max = Button(8), count = 8)]
+    buttons: [bool; 8],
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use usbd_human_interface_device::report_descriptor::HidReport;
+
+#[derive(HidReport)]
+#[hid(usage = Desktop::Joystick)]
+enum Report {
+    X(u8),
+}
+
+fn main() {}
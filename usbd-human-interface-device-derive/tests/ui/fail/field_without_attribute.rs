@@ -0,0 +1,11 @@
+use usbd_human_interface_device::report_descriptor::HidReport;
+
+#[derive(HidReport)]
+#[hid(usage = Desktop::Joystick)]
+struct Report {
+    #[hid(usage = Desktop::X)]
+    x: u8,
+    y: u8,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use usbd_human_interface_device::report_descriptor::HidReport;
+
+#[derive(HidReport)]
+#[hid(report_id = 1)]
+struct Report {
+    #[hid(usage = Desktop::X)]
+    x: u8,
+}
+
+fn main() {}
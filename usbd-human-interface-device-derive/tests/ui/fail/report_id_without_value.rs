@@ -0,0 +1,10 @@
+use usbd_human_interface_device::report_descriptor::HidReport;
+
+#[derive(HidReport)]
+#[hid(usage = Desktop::Joystick, report_id)]
+struct Report {
+    #[hid(usage = Desktop::X)]
+    x: u8,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use usbd_human_interface_device::report_descriptor::HidReport;
+
+#[derive(HidReport)]
+#[hid(usage = Desktop::Joystick)]
+struct Report {
+    #[hid(usage = Desktop::X, scale = 2)]
+    x: u8,
+}
+
+fn main() {}
@@ -0,0 +1,66 @@
+use usbd_human_interface_device::report_descriptor::fields::PackedStruct;
+use usbd_human_interface_device::report_descriptor::HidReport;
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, HidReport)]
+#[hid(usage = Desktop::Mouse, report_id = 4)]
+struct MouseReport {
+    #[hid(usage_min = Button(1), usage_max = Button(3), size = 1, padding = 5)]
+    buttons: [bool; 3],
+    #[hid(usage = Desktop::X, collection = Physical(Desktop::Pointer))]
+    x: i8,
+    #[hid(usage = Desktop::Y, collection = Physical(Desktop::Pointer))]
+    y: i8,
+    #[hid(usage = Desktop::Wheel, relative)]
+    wheel: i8,
+}
+
+fn main() {
+    #[rustfmt::skip]
+    let expected: &[u8] = &[
+        0x05, 0x01, // Usage Page (Generic Desktop)
+        0x09, 0x02, // Usage (Mouse)
+        0xa1, 0x01, // Collection (Application)
+        0x85, 0x04, //   Report ID (4)
+        0x05, 0x09, //   Usage Page (Button)
+        0x19, 0x01, //   Usage Minimum (1)
+        0x29, 0x03, //   Usage Maximum (3)
+        0x15, 0x00, //   Logical Minimum (0)
+        0x25, 0x01, //   Logical Maximum (1)
+        0x75, 0x01, //   Report Size (1)
+        0x95, 0x03, //   Report Count (3)
+        0x81, 0x02, //   Input (Data, Variable, Absolute)
+        0x75, 0x05, //   Report Size (5)
+        0x95, 0x01, //   Report Count (1)
+        0x81, 0x03, //   Input (Constant, Variable, Absolute)
+        0x05, 0x01, //   Usage Page (Generic Desktop)
+        0x09, 0x01, //   Usage (Pointer)
+        0xa1, 0x00, //   Collection (Physical)
+        0x09, 0x30, //     Usage (X)
+        0x09, 0x31, //     Usage (Y)
+        0x15, 0x80, //     Logical Minimum (-128)
+        0x25, 0x7f, //     Logical Maximum (127)
+        0x75, 0x08, //     Report Size (8)
+        0x95, 0x02, //     Report Count (2)
+        0x81, 0x02, //     Input (Data, Variable, Absolute)
+        0xc0,       //   End Collection
+        0x09, 0x38, //   Usage (Wheel)
+        0x15, 0x80, //   Logical Minimum (-128)
+        0x25, 0x7f, //   Logical Maximum (127)
+        0x75, 0x08, //   Report Size (8)
+        0x95, 0x01, //   Report Count (1)
+        0x81, 0x06, //   Input (Data, Variable, Relative)
+        0xc0,       // End Collection
+    ];
+    assert_eq!(MouseReport::DESCRIPTOR, expected);
+
+    let report = MouseReport {
+        buttons: [true, false, true],
+        x: -1,
+        y: 2,
+        wheel: -3,
+    };
+    let packed = report.pack().unwrap();
+    assert_eq!(packed, [0x04, 0b101, 0xff, 0x02, 0xfd]);
+    assert_eq!(MouseReport::unpack(&packed).unwrap(), report);
+    assert!(MouseReport::unpack(&[0x05, 0, 0, 0, 0]).is_err());
+}